@@ -3,7 +3,7 @@ use finders::searcher::Searches;
 use finders::{file_finder, searcher};
 use std::fs;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 fn create_test_files(dir: &PathBuf, num_files: usize, lines_per_file: usize) {
     fs::create_dir_all(dir).unwrap();
@@ -21,6 +21,18 @@ fn create_test_files(dir: &PathBuf, num_files: usize, lines_per_file: usize) {
     }
 }
 
+fn create_test_tree(dir: &Path, dirs: usize, subdirs: usize, files_per_dir: usize) {
+    for d in 0..dirs {
+        for s in 0..subdirs {
+            let sub_dir = dir.join(format!("dir_{}", d)).join(format!("sub_{}", s));
+            fs::create_dir_all(&sub_dir).unwrap();
+            for f in 0..files_per_dir {
+                fs::File::create(sub_dir.join(format!("file_{}.rs", f))).unwrap();
+            }
+        }
+    }
+}
+
 fn cleanup_test_files(dir: &PathBuf) {
     let _ = fs::remove_dir_all(dir);
 }
@@ -103,11 +115,52 @@ fn bench_file_finder(c: &mut Criterion) {
     cleanup_test_files(&temp_dir);
 }
 
+fn bench_directory_walk(c: &mut Criterion) {
+    let temp_dir = std::env::temp_dir().join("finders_bench_walk");
+    cleanup_test_files(&temp_dir);
+    create_test_tree(&temp_dir, 20, 10, 10);
+
+    let temp_dir_str = temp_dir.to_str().unwrap();
+
+    let mut group = c.benchmark_group("directory_walk");
+
+    group.bench_function("sequential", |b| {
+        b.iter(|| {
+            let finder = file_finder::Finder::new(Some(black_box(temp_dir_str)))
+                .unwrap()
+                .threads(1);
+            finder.find(None, false)
+        })
+    });
+
+    group.bench_function("parallel", |b| {
+        b.iter(|| {
+            let finder = file_finder::Finder::new(Some(black_box(temp_dir_str)))
+                .unwrap()
+                .threads(0);
+            finder.find(None, false)
+        })
+    });
+
+    group.bench_function("parallel_with_pattern", |b| {
+        b.iter(|| {
+            let finder = file_finder::Finder::new(Some(black_box(temp_dir_str)))
+                .unwrap()
+                .threads(0);
+            finder.find(Some(black_box("file_5")), false)
+        })
+    });
+
+    group.finish();
+    cleanup_test_files(&temp_dir);
+}
+
 criterion_group!(
     benches,
     bench_searcher_search_line,
     bench_regex_searcher_search_line,
     bench_searcher_search_content,
-    bench_file_finder
+    bench_file_finder,
+    bench_directory_walk
 );
 criterion_main!(benches);
@@ -1,13 +1,21 @@
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use clap::Parser;
 use rayon::ThreadPoolBuilder;
+use std::fs::File;
+use std::io::{self, BufReader, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 
-use finders::file_finder;
+use finders::exec::{CommandTemplate, exec_batch, exec_each};
+use finders::file_finder::{EntryKind, Finder, MetadataFilter, TypeRegistry, read_file_list};
 use finders::output::{
-    ColourMode, CountOutput, FilesOnlyOutput, JsonOutput, Outputs, StandardOutput,
+    ColourMode, ColourScheme, CountOutput, CsvColumn, CsvOutput, FilesOnlyOutput, HyperlinkFormat,
+    JsonFormat, JsonLinesOutput, JsonOutput, LongOutput, Outputs, RgJsonOutput, SarifOutput,
+    StandardOutput, Template, TemplateOutput, UserColourSpec, VimgrepOutput,
 };
-use finders::search_files;
-use finders::searcher;
+use finders::searcher::{self, Searches};
+use finders::sort::{SortKey, SortOrder, sort_paths};
+use finders::{search_files, search_files_sorted};
 
 const FINDERS: &str = r#"
 ___________.__            .___    __________  _________
@@ -21,13 +29,90 @@ ___________.__            .___    __________  _________
 #[command(arg_required_else_help = true)]
 #[command(author, version, about, long_about = None, before_help = FINDERS)]
 struct Cli {
-    /// Optional path to operate on, defaults to CWD
-    path: Option<String>,
+    /// Paths to search, defaults to CWD
+    #[arg(conflicts_with = "files_from")]
+    paths: Vec<String>,
+
+    /// Search the files listed in FILE (one per line, '-' for stdin) instead of walking
+    #[arg(long, value_name = "FILE")]
+    files_from: Option<String>,
+
+    /// Entries in the --files-from list are separated by NUL bytes, not newlines
+    #[arg(long, requires = "files_from")]
+    null_data: bool,
 
     /// File pattern to filter results
     #[arg(short, long)]
     file_pattern: Option<String>,
 
+    /// Only search files of the given type (see --type-list)
+    #[arg(short = 't', long = "type", value_name = "TYPE")]
+    file_type: Vec<String>,
+
+    /// Skip files of the given type (see --type-list)
+    #[arg(short = 'T', long = "type-not", value_name = "TYPE")]
+    file_type_not: Vec<String>,
+
+    /// Add a file type glob, e.g. 'proto:*.proto' (extends existing types)
+    #[arg(long, value_name = "NAME:GLOB")]
+    type_add: Vec<String>,
+
+    /// Print all known file types and their globs, then exit
+    #[arg(long)]
+    type_list: bool,
+
+    /// Only list entries of this kind: f (file), d (dir), l (symlink), e (empty), s (socket), p (pipe)
+    #[arg(long, value_name = "KIND")]
+    kind: Vec<EntryKind>,
+
+    /// Descend at most this many directory levels (1 = only the root's entries)
+    #[arg(long, value_name = "DEPTH")]
+    max_depth: Option<usize>,
+
+    /// Skip entries shallower than this many levels (1 = the root's entries)
+    #[arg(long, value_name = "DEPTH")]
+    min_depth: Option<usize>,
+
+    /// Include hidden files and directories (.git is still skipped)
+    #[arg(short = 'H', long)]
+    hidden: bool,
+
+    /// Follow symbolic links (default)
+    #[arg(short = 'L', long, overrides_with = "no_follow")]
+    follow: bool,
+
+    /// Don't follow symbolic links
+    #[arg(long, overrides_with = "follow")]
+    no_follow: bool,
+
+    /// Don't descend into directories on other file systems (mount points)
+    #[arg(short = 'x', long)]
+    one_file_system: bool,
+
+    /// Filter by file size, e.g. +10M (larger), -1k (smaller) or 512 (exact)
+    #[arg(long, value_name = "SIZE", allow_hyphen_values = true)]
+    size: Vec<String>,
+
+    /// Only files modified within a duration (2d, 3h) or since a date (2026-01-01)
+    #[arg(long, value_name = "TIME")]
+    changed_within: Option<String>,
+
+    /// Only files modified before a duration ago (2d, 3h) or a date (2026-01-01)
+    #[arg(long, value_name = "TIME")]
+    changed_before: Option<String>,
+
+    /// Only files modified more recently than the given file
+    #[arg(long, value_name = "FILE")]
+    newer: Option<String>,
+
+    /// Filter by octal permission mode: 644 (exact), -644 (all bits), /111 (any bit)
+    #[arg(long, value_name = "MODE", allow_hyphen_values = true)]
+    perm: Option<String>,
+
+    /// Only executable files
+    #[arg(long)]
+    executable: bool,
+
     /// Search pattern to match in result files
     #[arg(short, long)]
     search_pattern: Option<String>,
@@ -52,6 +137,22 @@ struct Cli {
     #[arg(long, conflicts_with = "colour")]
     no_colour: bool,
 
+    /// Colour spec like path:fg:magenta, line:style:bold, match:bg:#1e90ff or path:none (repeatable; overrides FINDERS_COLORS)
+    #[arg(long, value_name = "SPEC")]
+    colors: Vec<UserColourSpec>,
+
+    /// Print each file's path once above its matches (default when printing to a terminal)
+    #[arg(long, overrides_with = "no_heading")]
+    heading: bool,
+
+    /// Print the path on every matching line, even on a terminal
+    #[arg(long, overrides_with = "heading")]
+    no_heading: bool,
+
+    /// Make paths clickable with OSC 8 hyperlinks: file, vscode, idea or a template using {path}, {line}, {column}
+    #[arg(long, value_name = "FORMAT")]
+    hyperlink_format: Option<HyperlinkFormat>,
+
     /// Output only file paths with matches (like grep -l)
     #[arg(short = 'l', long, conflicts_with = "count")]
     files_with_matches: bool,
@@ -64,37 +165,353 @@ struct Cli {
     #[arg(long, conflicts_with_all = ["files_with_matches", "count"])]
     json: bool,
 
-    /// Number of threads to use (0 = auto-detect)
+    /// JSON layout: finders (default) or rg, for tools that parse ripgrep's --json (implies --json)
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        conflicts_with_all = ["files_with_matches", "count", "jsonl", "print0"]
+    )]
+    json_format: Option<JsonFormat>,
+
+    /// Output matches as a SARIF 2.1.0 log for code-scanning tools (needs -s or -r)
+    #[arg(
+        long,
+        conflicts_with_all = ["json", "json_format", "jsonl", "files_with_matches", "count", "long", "print0"]
+    )]
+    sarif: bool,
+
+    /// Output a CSV table with a header row (see --columns)
+    #[arg(
+        long,
+        group = "table",
+        conflicts_with_all = [
+            "tsv", "json", "json_format", "jsonl", "sarif", "files_with_matches", "count", "long", "print0"
+        ]
+    )]
+    csv: bool,
+
+    /// Output a tab-separated table with a header row (see --columns)
+    #[arg(
+        long,
+        group = "table",
+        conflicts_with_all = [
+            "csv", "json", "json_format", "jsonl", "sarif", "files_with_matches", "count", "long", "print0"
+        ]
+    )]
+    tsv: bool,
+
+    /// Output one path:line:column:content entry per match for Vim/Emacs quickfix (sorted by path)
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "json", "json_format", "jsonl", "sarif", "csv", "tsv", "files_with_matches", "count", "long",
+            "print0"
+        ]
+    )]
+    vimgrep: bool,
+
+    /// Output template, e.g. '{path}:{line}:{col} {match}' (see the docs for all placeholders)
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        conflicts_with_all = [
+            "json", "json_format", "jsonl", "sarif", "csv", "tsv", "vimgrep", "files_with_matches",
+            "count", "long", "print0"
+        ]
+    )]
+    format: Option<Template>,
+
+    /// Template written before each file's matches with --format
+    #[arg(long, value_name = "TEMPLATE", requires = "format")]
+    format_header: Option<Template>,
+
+    /// Template written after each file's matches with --format; can use {count} and {lines}
+    #[arg(long, value_name = "TEMPLATE", requires = "format")]
+    format_footer: Option<Template>,
+
+    /// Columns for --csv/--tsv: path, line, column, match, content, size, modified, permissions
+    #[arg(long, value_name = "LIST", value_delimiter = ',', requires = "table")]
+    columns: Vec<CsvColumn>,
+
+    /// Stream results as JSON Lines: one object per file begin, match, file end and summary
+    #[arg(
+        long,
+        conflicts_with_all = ["json", "files_with_matches", "count", "long", "print0"]
+    )]
+    jsonl: bool,
+
+    /// List files with permissions, size and modification time, like ls -l (adds metadata to --json)
+    #[arg(
+        long,
+        conflicts_with_all = ["search_pattern", "regex_pattern", "files_with_matches", "count", "print0"]
+    )]
+    long: bool,
+
+    /// Follow each printed path with a NUL byte instead of a newline or ':' (for xargs -0)
+    #[arg(short = '0', long, conflicts_with = "json")]
+    print0: bool,
+
+    /// Run a command on each file found, in parallel (end with ';' to add more options)
+    ///
+    /// Placeholders: {} path, {/} file name, {//} parent directory, {/.} file name
+    /// without extension, {.} path without extension. The path is appended if none
+    /// are used.
+    #[arg(
+        long,
+        num_args = 1..,
+        allow_hyphen_values = true,
+        value_terminator = ";",
+        value_name = "CMD",
+        conflicts_with_all = [
+            "search_pattern", "regex_pattern", "json", "json_format", "jsonl", "sarif", "csv", "tsv",
+            "vimgrep", "format", "files_with_matches", "count", "print0", "long", "exec_batch"
+        ]
+    )]
+    exec: Option<Vec<String>>,
+
+    /// Run a command once with all files found as arguments (same placeholders as --exec)
+    #[arg(
+        long,
+        num_args = 1..,
+        allow_hyphen_values = true,
+        value_terminator = ";",
+        value_name = "CMD",
+        conflicts_with_all = [
+            "search_pattern", "regex_pattern", "json", "json_format", "jsonl", "sarif", "csv", "tsv",
+            "vimgrep", "format", "files_with_matches", "count", "print0", "long"
+        ]
+    )]
+    exec_batch: Option<Vec<String>>,
+
+    /// Number of threads for walking and searching (0 = auto-detect); unless sorted, output order varies between runs
     #[arg(short = 'j', long, default_value = "0")]
     threads: usize,
+
+    /// Sort results by path, mtime, size or matches (output is deterministic)
+    #[arg(long, value_name = "KEY", conflicts_with = "sortr")]
+    sort: Option<SortKey>,
+
+    /// Sort results in descending order by path, mtime, size or matches
+    #[arg(long, value_name = "KEY", conflicts_with = "sort")]
+    sortr: Option<SortKey>,
 }
 
-fn main() -> Result<()> {
+impl Cli {
+    /// Whether to group matches under a heading per file: --heading or
+    /// --no-heading if given, otherwise only when stdout is a terminal
+    fn heading(&self) -> bool {
+        if self.heading || self.no_heading {
+            self.heading
+        } else {
+            io::stdout().is_terminal()
+        }
+    }
+
+    /// The requested sort order, if any
+    ///
+    /// --vimgrep sorts by path unless told otherwise, so quickfix lists are
+    /// stable between runs.
+    fn sort_order(&self) -> Option<SortOrder> {
+        match (self.sort, self.sortr) {
+            (Some(key), _) => Some(SortOrder::new(key, false)),
+            (None, Some(key)) => Some(SortOrder::new(key, true)),
+            (None, None) if self.vimgrep => Some(SortOrder::new(SortKey::Path, false)),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Build the metadata filters requested on the command line
+fn metadata_filters(cli: &Cli) -> Result<Vec<MetadataFilter>> {
+    let mut filters = Vec::new();
+
+    for size in &cli.size {
+        filters.push(MetadataFilter::parse_size(size)?);
+    }
+    if let Some(time) = cli.changed_within.as_deref() {
+        filters.push(MetadataFilter::parse_changed_within(time)?);
+    }
+    if let Some(time) = cli.changed_before.as_deref() {
+        filters.push(MetadataFilter::parse_changed_before(time)?);
+    }
+    if let Some(reference) = cli.newer.as_deref() {
+        filters.push(MetadataFilter::newer_than(Path::new(reference))?);
+    }
+    if let Some(mode) = cli.perm.as_deref() {
+        filters.push(MetadataFilter::parse_permissions(mode)?);
+    }
+    if cli.executable {
+        filters.push(MetadataFilter::Executable);
+    }
+
+    Ok(filters)
+}
+
+/// Reject --format templates using placeholders that have no value where
+/// they're used
+fn check_templates(cli: &Cli, searching: bool) -> Result<()> {
+    let templates = [
+        ("--format", &cli.format),
+        ("--format-header", &cli.format_header),
+        ("--format-footer", &cli.format_footer),
+    ];
+    for (flag, template) in templates {
+        let Some(template) = template else {
+            continue;
+        };
+        if template.uses_match_fields() && (!searching || flag != "--format") {
+            bail!(
+                "{} can't use match placeholders like {{line}} or {{match}}{}",
+                flag,
+                if searching { "" } else { " when listing files" }
+            );
+        }
+        if template.uses_count_fields() && flag != "--format-footer" {
+            bail!("{{count}} and {{lines}} are only available in --format-footer");
+        }
+    }
+    Ok(())
+}
+
+/// The --columns for CSV/TSV output, or the defaults for searching or listing
+fn csv_columns(cli: &Cli, searching: bool) -> Result<Vec<CsvColumn>> {
+    if cli.columns.is_empty() {
+        let defaults = if searching {
+            CsvColumn::SEARCH
+        } else {
+            CsvColumn::LISTING
+        };
+        return Ok(defaults.to_vec());
+    }
+
+    if !searching && let Some(column) = cli.columns.iter().find(|column| column.is_match_column()) {
+        bail!(
+            "the '{}' column only applies when searching (-s or -r)",
+            column.name()
+        );
+    }
+    Ok(cli.columns.clone())
+}
+
+/// Read the --files-from list from a file, or stdin for '-'
+fn read_files_from(list: &str, null_data: bool) -> Result<Vec<PathBuf>> {
+    if list == "-" {
+        return read_file_list(io::stdin().lock(), null_data)
+            .context("reading file list from stdin");
+    }
+
+    let file = File::open(list).with_context(|| format!("opening file list '{}'", list))?;
+    read_file_list(BufReader::new(file), null_data)
+        .with_context(|| format!("reading file list '{}'", list))
+}
+
+fn main() -> Result<ExitCode> {
     let cli = Cli::parse();
 
-    // Grab finder values from the command line
-    let finder =
-        file_finder::Finder::new(cli.path.as_deref()).context("initializing file finder")?;
-    let file_pattern = cli.file_pattern.as_deref();
+    // Build the file type registry, including any user-defined types
+    let mut type_registry = TypeRegistry::new();
+    for definition in &cli.type_add {
+        type_registry
+            .add(definition)
+            .context("adding file type definition")?;
+    }
 
-    // Determine if verbose or not
-    let verbose = cli.verbose;
+    if cli.type_list {
+        for (name, globs) in type_registry.iter() {
+            println!("{}: {}", name, globs.join(", "));
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
 
-    // Get paths - collect into Vec for potential parallel processing
-    let paths = finder.find(file_pattern, verbose);
+    // Grab finder values from the command line
+    let finder = match cli.files_from.as_deref() {
+        Some(list) => Finder::with_files(read_files_from(list, cli.null_data)?),
+        None => Finder::with_roots(cli.paths.iter().map(String::as_str))
+            .context("initializing file finder")?,
+    };
+    let searching = cli.search_pattern.is_some() || cli.regex_pattern.is_some();
+    if searching && cli.kind.iter().any(|kind| *kind != EntryKind::File) {
+        bail!("--kind can only select regular files when searching file contents");
+    }
 
+    let mut finder = finder
+        .kinds(cli.kind.clone())
+        .filters(metadata_filters(&cli).context("parsing file filters")?)
+        .min_depth(cli.min_depth)
+        .max_depth(cli.max_depth)
+        .hidden(cli.hidden)
+        .follow_links(!cli.no_follow)
+        .same_file_system(cli.one_file_system)
+        .threads(cli.threads);
+    if !cli.file_type.is_empty() || !cli.file_type_not.is_empty() {
+        finder = finder.types(
+            type_registry
+                .matcher(&cli.file_type, &cli.file_type_not)
+                .context("selecting file types")?,
+        );
+    }
     // Determine colour mode from flags and environment
     let colour_mode = ColourMode::from_env(cli.colour, cli.no_colour);
+    let colours = ColourScheme::from_env(&cli.colors)?;
+
+    let json_format = cli.json_format.or(cli.json.then_some(JsonFormat::Finders));
+    if cli.long && json_format == Some(JsonFormat::Rg) {
+        bail!("--long can't be combined with --json-format rg");
+    }
+
+    // Sorted results arrive in order, which collecting outputs must keep
+    let sorted = cli.sort_order().is_some();
 
     // Create output handler based on output mode flags
-    let mut output: Box<dyn Outputs> = if cli.json {
-        Box::new(JsonOutput::new())
+    let mut output: Box<dyn Outputs> = if cli.jsonl {
+        Box::new(JsonLinesOutput::new())
+    } else if cli.vimgrep {
+        Box::new(VimgrepOutput::new())
+    } else if let Some(template) = &cli.format {
+        check_templates(&cli, searching)?;
+        Box::new(
+            TemplateOutput::new(template.clone(), colour_mode)
+                .header(cli.format_header.clone())
+                .footer(cli.format_footer.clone())
+                .colours(colours),
+        )
+    } else if cli.csv || cli.tsv {
+        Box::new(CsvOutput::new(csv_columns(&cli, searching)?).tab_separated(cli.tsv))
+    } else if cli.sarif {
+        match (&cli.search_pattern, &cli.regex_pattern) {
+            (Some(query), _) => Box::new(SarifOutput::new(query, false).sorted(sorted)),
+            (None, Some(pattern)) => Box::new(SarifOutput::new(pattern, true).sorted(sorted)),
+            (None, None) => bail!("--sarif needs a search pattern (-s or -r)"),
+        }
+    } else if let Some(format) = json_format {
+        match format {
+            JsonFormat::Finders => Box::new(JsonOutput::new().metadata(cli.long).sorted(sorted)),
+            JsonFormat::Rg => Box::new(RgJsonOutput::new()),
+        }
+    } else if cli.long {
+        Box::new(LongOutput::new(colour_mode).colours(colours))
     } else if cli.files_with_matches {
-        Box::new(FilesOnlyOutput::new(colour_mode))
+        Box::new(
+            FilesOnlyOutput::new(colour_mode)
+                .colours(colours)
+                .null_separated(cli.print0)
+                .hyperlinks(cli.hyperlink_format.clone()),
+        )
     } else if cli.count {
-        Box::new(CountOutput::new(colour_mode))
+        Box::new(
+            CountOutput::new(colour_mode)
+                .colours(colours)
+                .null_separated(cli.print0)
+                .hyperlinks(cli.hyperlink_format.clone()),
+        )
     } else {
-        Box::new(StandardOutput::new(colour_mode))
+        Box::new(
+            StandardOutput::new(colour_mode)
+                .colours(colours)
+                .null_separated(cli.print0)
+                .heading(cli.heading())
+                .hyperlinks(cli.hyperlink_format.clone()),
+        )
     };
 
     // Configure thread pool based on --threads flag
@@ -109,48 +526,98 @@ fn main() -> Result<()> {
             .context("creating thread pool")?;
 
         // Run search within custom pool
-        pool.install(|| -> Result<()> {
-            if let Some(query) = cli.search_pattern.as_deref() {
-                let case_insensitive = cli.case_insensitive;
-                let searcher = searcher::Searcher::new(query, case_insensitive);
-
-                search_files(searcher, paths, verbose, &mut *output)
-                    .context("searching files for pattern")?;
-            } else if let Some(pattern) = cli.regex_pattern.as_deref() {
-                let re_searcher =
-                    searcher::ReSearcher::new(pattern).context("compiling regex pattern")?;
-
-                search_files(re_searcher, paths, verbose, &mut *output)
-                    .context("searching files for pattern")?;
-            } else {
-                // File-only mode (no search pattern)
-                for path in paths {
-                    output.write_file(&path);
-                }
-            }
-            Ok(())
-        })?;
+        pool.install(|| run(&cli, &finder, &mut *output))
     } else {
         // Auto-detect: use global thread pool (default behavior)
-        if let Some(query) = cli.search_pattern.as_deref() {
-            let case_insensitive = cli.case_insensitive;
-            let searcher = searcher::Searcher::new(query, case_insensitive);
-
-            search_files(searcher, paths, verbose, &mut *output)
-                .context("searching files for pattern")?;
-        } else if let Some(pattern) = cli.regex_pattern.as_deref() {
-            let re_searcher =
-                searcher::ReSearcher::new(pattern).context("compiling regex pattern")?;
-
-            search_files(re_searcher, paths, verbose, &mut *output)
-                .context("searching files for pattern")?;
-        } else {
-            // File-only mode (no search pattern)
+        run(&cli, &finder, &mut *output)
+    }
+}
+
+/// Stream files from the finder into the searcher, or straight to the
+/// output in file-only mode, so results appear while the tree is walked
+///
+/// Fails the process if any command run by --exec or --exec-batch fails.
+fn run(cli: &Cli, finder: &Finder, output: &mut dyn Outputs) -> Result<ExitCode> {
+    let file_pattern = cli.file_pattern.as_deref();
+
+    // Determine if verbose or not
+    let verbose = cli.verbose;
+
+    if let Some(query) = cli.search_pattern.as_deref() {
+        let case_insensitive = cli.case_insensitive;
+        let searcher = searcher::Searcher::new(query, case_insensitive);
+
+        search(cli, finder, searcher, output).context("searching files for pattern")?;
+    } else if let Some(pattern) = cli.regex_pattern.as_deref() {
+        let re_searcher = searcher::ReSearcher::new(pattern).context("compiling regex pattern")?;
+
+        search(cli, finder, re_searcher, output).context("searching files for pattern")?;
+    } else if let Some(command) = &cli.exec {
+        let template = CommandTemplate::new(command).context("parsing --exec command")?;
+        let succeeded = finder
+            .stream(file_pattern, verbose, |paths| exec_each(&template, paths))
+            .context("executing command on found files")?;
+
+        return Ok(exit_code(succeeded));
+    } else if let Some(command) = &cli.exec_batch {
+        let template = CommandTemplate::new(command).context("parsing --exec-batch command")?;
+        // Default to path order so the argument list is stable between runs
+        let order = cli
+            .sort_order()
+            .unwrap_or(SortOrder::new(SortKey::Path, false));
+        let paths = sort_paths(finder.find(file_pattern, verbose), order);
+        let succeeded =
+            exec_batch(&template, &paths).context("executing command on found files")?;
+
+        return Ok(exit_code(succeeded));
+    } else if let Some(order) = cli.sort_order() {
+        // Sorting needs every path before the first can be written
+        let paths = finder.find(file_pattern, verbose);
+        for path in sort_paths(paths, order) {
+            output.write_file(&path);
+        }
+        output.finalize();
+    } else {
+        // File-only mode (no search pattern)
+        finder.stream(file_pattern, verbose, |paths| {
             for path in paths {
                 output.write_file(&path);
             }
-        }
+        });
+        output.finalize();
     }
 
-    Ok(())
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Search the files found by `finder`, in the requested order if any
+fn search(
+    cli: &Cli,
+    finder: &Finder,
+    searcher: impl Searches + Sync,
+    output: &mut dyn Outputs,
+) -> Result<()> {
+    let file_pattern = cli.file_pattern.as_deref();
+    let verbose = cli.verbose;
+
+    match cli.sort_order() {
+        Some(order) => search_files_sorted(
+            searcher,
+            finder.find(file_pattern, verbose),
+            order,
+            verbose,
+            output,
+        ),
+        None => finder.stream(file_pattern, verbose, |paths| {
+            search_files(searcher, paths, verbose, output)
+        }),
+    }
+}
+
+fn exit_code(succeeded: bool) -> ExitCode {
+    if succeeded {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
 }
@@ -0,0 +1,346 @@
+use anyhow::{Context, Result, bail};
+use rayon::prelude::*;
+use std::ffi::{OsStr, OsString};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+/// Part of a command-line argument
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Text(String),
+    /// `{}`: the path as found
+    Path,
+    /// `{/}`: the file name
+    Basename,
+    /// `{//}`: the parent directory
+    Parent,
+    /// `{/.}`: the file name without its extension
+    BasenameNoExt,
+    /// `{.}`: the path without its extension
+    PathNoExt,
+}
+
+/// Placeholders in the order they're recognised, longest first
+const PLACEHOLDERS: &[(&str, Token)] = &[
+    ("{//}", Token::Parent),
+    ("{/.}", Token::BasenameNoExt),
+    ("{/}", Token::Basename),
+    ("{.}", Token::PathNoExt),
+    ("{}", Token::Path),
+];
+
+/// A command to run on found files (like fd's --exec)
+///
+/// Arguments may contain the placeholders `{}`, `{/}`, `{//}`, `{/.}` and
+/// `{.}`. If none are used, the path is appended as the last argument.
+#[derive(Debug, Clone)]
+pub struct CommandTemplate {
+    args: Vec<Vec<Token>>,
+}
+
+impl CommandTemplate {
+    /// Parse a command and its arguments
+    pub fn new(command: &[String]) -> Result<Self> {
+        if command.is_empty() {
+            bail!("empty command");
+        }
+
+        let mut args: Vec<Vec<Token>> = command.iter().map(|arg| tokenize(arg)).collect();
+        if !args
+            .iter()
+            .flatten()
+            .any(|token| !matches!(token, Token::Text(_)))
+        {
+            args.push(vec![Token::Path]);
+        }
+        let template = CommandTemplate { args };
+        template.program()?;
+
+        Ok(template)
+    }
+
+    /// Build the command for a single path
+    fn command(&self, path: &Path) -> Command {
+        self.command_for(&[path])
+    }
+
+    /// Build the command for several paths
+    ///
+    /// Arguments containing placeholders are repeated once per path.
+    fn command_for(&self, paths: &[&Path]) -> Command {
+        let mut command = Command::new(expand(&self.args[0], Path::new("")));
+        for arg in &self.args[1..] {
+            if arg.iter().all(|token| matches!(token, Token::Text(_))) {
+                command.arg(expand(arg, Path::new("")));
+            } else {
+                command.args(paths.iter().map(|path| expand(arg, path)));
+            }
+        }
+        command
+    }
+
+    /// The program being run, for error messages
+    fn program(&self) -> Result<&str> {
+        match self.args[0].as_slice() {
+            [Token::Text(program)] => Ok(program),
+            _ => bail!("the program name can't contain placeholders"),
+        }
+    }
+
+    /// Split paths into runs whose command lines each fit in `limit` bytes
+    ///
+    /// Every batch holds at least one path, so a single path longer than the
+    /// limit is still tried on its own.
+    fn batches<'a>(&self, paths: &'a [&'a Path], limit: usize) -> Vec<&'a [&'a Path]> {
+        let (fixed, repeated): (Vec<_>, Vec<_>) = self
+            .args
+            .iter()
+            .partition(|arg| arg.iter().all(|token| matches!(token, Token::Text(_))));
+        let budget = limit.saturating_sub(
+            fixed
+                .iter()
+                .map(|arg| arg_size(&expand(arg, Path::new(""))))
+                .sum(),
+        );
+
+        let mut batches = Vec::new();
+        let mut start = 0;
+        let mut size = 0;
+        for (i, path) in paths.iter().enumerate() {
+            let path_size: usize = repeated
+                .iter()
+                .map(|arg| arg_size(&expand(arg, path)))
+                .sum();
+            if i > start && size + path_size > budget {
+                batches.push(&paths[start..i]);
+                start = i;
+                size = 0;
+            }
+            size += path_size;
+        }
+        if start < paths.len() {
+            batches.push(&paths[start..]);
+        }
+        batches
+    }
+}
+
+/// Bytes an argument takes from the system limit: the string, its NUL and
+/// the `argv` pointer
+fn arg_size(arg: &OsStr) -> usize {
+    arg.len() + 1 + std::mem::size_of::<usize>()
+}
+
+/// Room for arguments when batching, leaving the environment its share
+///
+/// 128 KiB is the smallest `ARG_MAX` POSIX systems use in practice (Windows
+/// allows 32 KiB for the whole command line); `find` and `xargs` also hold
+/// back 2 KiB of headroom.
+fn arg_limit() -> usize {
+    let max: usize = if cfg!(windows) { 32 * 1024 } else { 128 * 1024 };
+    let environment: usize = std::env::vars_os()
+        .map(|(key, value)| arg_size(&key) + value.len() + 1)
+        .sum();
+    max.saturating_sub(environment).saturating_sub(2048)
+}
+
+/// Run the command once per path, in parallel on the current rayon pool
+///
+/// Each command's output is captured and written in one piece once it
+/// exits, so output from concurrent commands never interleaves. Returns
+/// whether every command succeeded; a command that can't be started is
+/// reported on stderr and counts as a failure without stopping the rest.
+pub fn exec_each<I>(template: &CommandTemplate, paths: I) -> Result<bool>
+where
+    I: IntoIterator<Item = PathBuf>,
+    I::IntoIter: Send,
+{
+    let program = template.program()?;
+    let results: Vec<bool> = paths
+        .into_iter()
+        .par_bridge()
+        .map(
+            |path| match template.command(&path).stdin(Stdio::null()).output() {
+                Ok(output) => {
+                    write_output(&output);
+                    output.status.success()
+                }
+                Err(e) => {
+                    eprintln!("Error running '{}' on {}: {}", program, path.display(), e);
+                    false
+                }
+            },
+        )
+        .collect();
+
+    Ok(results.into_iter().all(|succeeded| succeeded))
+}
+
+/// Run the command with every path as arguments
+///
+/// Like `find -exec {} +`, the paths are split over as many runs as needed
+/// to stay under the system's argument limit; the runs happen one after
+/// another, in order. Each inherits stdin and stdout. Returns whether every
+/// run succeeded; nothing is run when there are no paths.
+pub fn exec_batch(template: &CommandTemplate, paths: &[PathBuf]) -> Result<bool> {
+    let program = template.program()?;
+    let paths: Vec<&Path> = paths.iter().map(PathBuf::as_path).collect();
+
+    let mut succeeded = true;
+    for batch in template.batches(&paths, arg_limit()) {
+        let status = template
+            .command_for(batch)
+            .status()
+            .with_context(|| format!("running '{}'", program))?;
+        succeeded &= status.success();
+    }
+
+    Ok(succeeded)
+}
+
+/// Write a finished command's captured output while holding both stream locks
+fn write_output(output: &Output) {
+    let mut stdout = io::stdout().lock();
+    let mut stderr = io::stderr().lock();
+    let _ = stdout.write_all(&output.stdout);
+    let _ = stdout.flush();
+    let _ = stderr.write_all(&output.stderr);
+}
+
+fn tokenize(arg: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut text = String::new();
+    let mut rest = arg;
+
+    while let Some(c) = rest.chars().next() {
+        match PLACEHOLDERS.iter().find(|(p, _)| rest.starts_with(p)) {
+            Some((placeholder, token)) => {
+                if !text.is_empty() {
+                    tokens.push(Token::Text(std::mem::take(&mut text)));
+                }
+                tokens.push(token.clone());
+                rest = &rest[placeholder.len()..];
+            }
+            None => {
+                text.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    if !text.is_empty() || tokens.is_empty() {
+        tokens.push(Token::Text(text));
+    }
+    tokens
+}
+
+fn expand(tokens: &[Token], path: &Path) -> OsString {
+    let mut arg = OsString::new();
+    for token in tokens {
+        match token {
+            Token::Text(text) => arg.push(text),
+            Token::Path => arg.push(path),
+            Token::Basename => arg.push(path.file_name().unwrap_or(path.as_os_str())),
+            Token::Parent => arg.push(parent(path)),
+            Token::BasenameNoExt => arg.push(path.file_stem().unwrap_or(path.as_os_str())),
+            Token::PathNoExt => arg.push(path.with_extension("")),
+        }
+    }
+    arg
+}
+
+/// Parent directory, or `.` for a bare file name
+fn parent(path: &Path) -> &OsStr {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.as_os_str(),
+        _ => OsStr::new("."),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(command: &Command) -> Vec<String> {
+        std::iter::once(command.get_program())
+            .chain(command.get_args())
+            .map(|arg| arg.to_string_lossy().to_string())
+            .collect()
+    }
+
+    fn template(command: &[&str]) -> CommandTemplate {
+        let command: Vec<String> = command.iter().map(|arg| arg.to_string()).collect();
+        CommandTemplate::new(&command).unwrap()
+    }
+
+    #[test]
+    fn placeholders_expand() {
+        let template = template(&["echo", "{}", "{/}", "{//}", "{/.}", "{.}", "x{/.}.bak"]);
+        let command = template.command(Path::new("src/output/json.rs"));
+
+        assert_eq!(
+            args(&command),
+            vec![
+                "echo",
+                "src/output/json.rs",
+                "json.rs",
+                "src/output",
+                "json",
+                "src/output/json",
+                "xjson.bak"
+            ]
+        );
+        assert_eq!(args(&template.command(Path::new("lib.rs")))[3], ".");
+    }
+
+    #[test]
+    fn path_appended_without_placeholders() {
+        let command = template(&["wc", "-l"]).command(Path::new("a.txt"));
+        assert_eq!(args(&command), vec!["wc", "-l", "a.txt"]);
+    }
+
+    #[test]
+    fn batch_repeats_placeholder_arguments() {
+        let paths = [Path::new("a.rs"), Path::new("dir/b.rs")];
+
+        let appended = template(&["ls", "-l"]).command_for(&paths);
+        assert_eq!(args(&appended), vec!["ls", "-l", "a.rs", "dir/b.rs"]);
+
+        let named = template(&["tar", "-cf", "out.tar", "{/}"]).command_for(&paths);
+        assert_eq!(args(&named), vec!["tar", "-cf", "out.tar", "a.rs", "b.rs"]);
+    }
+
+    #[test]
+    fn batches_stay_under_the_limit() {
+        let paths = [Path::new("a.rs"), Path::new("b.rs"), Path::new("c.rs")];
+        let per_path = arg_size(OsStr::new("a.rs"));
+        let fixed = arg_size(OsStr::new("ls")) + arg_size(OsStr::new("-l"));
+        let template = template(&["ls", "-l"]);
+
+        assert_eq!(
+            template.batches(&paths, fixed + 3 * per_path),
+            vec![&paths[..]]
+        );
+        assert_eq!(
+            template.batches(&paths, fixed + 2 * per_path),
+            vec![&paths[..2], &paths[2..]]
+        );
+        // A path too long for any batch still runs, on its own
+        assert_eq!(template.batches(&paths, 0).len(), 3);
+        assert!(template.batches(&[], 0).is_empty());
+    }
+
+    #[test]
+    fn unstartable_command_is_a_failure() {
+        let template = template(&["finder-test-no-such-program"]);
+        let paths = vec![PathBuf::from("a.txt"), PathBuf::from("b.txt")];
+
+        assert!(!exec_each(&template, paths).unwrap());
+    }
+
+    #[test]
+    fn invalid_templates_rejected() {
+        assert!(CommandTemplate::new(&[]).is_err());
+        assert!(CommandTemplate::new(&["{}".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,85 @@
+use anyhow::{Context, Result};
+use std::io::BufRead;
+use std::path::PathBuf;
+
+/// Read a list of file paths, one per line or NUL-separated with `null_data`
+///
+/// Empty entries are ignored, as is a trailing `\r` on newline-separated
+/// lists. On Unix paths are taken byte-for-byte, so names that aren't
+/// valid UTF-8 survive the round trip.
+pub fn read_file_list(mut reader: impl BufRead, null_data: bool) -> Result<Vec<PathBuf>> {
+    let separator = if null_data { b'\0' } else { b'\n' };
+    let mut paths = Vec::new();
+    let mut entry = Vec::new();
+
+    loop {
+        entry.clear();
+        let read = reader
+            .read_until(separator, &mut entry)
+            .with_context(|| format!("reading entry {} of file list", paths.len() + 1))?;
+        if read == 0 {
+            break;
+        }
+
+        if entry.last() == Some(&separator) {
+            entry.pop();
+        }
+        if !null_data && entry.last() == Some(&b'\r') {
+            entry.pop();
+        }
+        if !entry.is_empty() {
+            paths.push(path_from_bytes(&entry)?);
+        }
+    }
+
+    Ok(paths)
+}
+
+#[cfg(unix)]
+fn path_from_bytes(bytes: &[u8]) -> Result<PathBuf> {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+    Ok(PathBuf::from(OsStr::from_bytes(bytes)))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: &[u8]) -> Result<PathBuf> {
+    let path = std::str::from_utf8(bytes).context("file list entry isn't valid UTF-8")?;
+    Ok(PathBuf::from(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_newline_separated_list() -> Result<()> {
+        let list = b"src/lib.rs\r\n\nmy file.txt\nlast.rs";
+        let paths = read_file_list(&list[..], false)?;
+
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("src/lib.rs"),
+                PathBuf::from("my file.txt"),
+                PathBuf::from("last.rs")
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn read_null_separated_list() -> Result<()> {
+        let list = b"line\nbreak.txt\0with space.rs\0\0";
+        let paths = read_file_list(&list[..], true)?;
+
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("line\nbreak.txt"),
+                PathBuf::from("with space.rs")
+            ]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,362 @@
+use anyhow::{Context, Result, bail};
+use regex::bytes::Regex;
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+
+/// Built-in file types as (name, globs) pairs
+const DEFAULT_TYPES: &[(&str, &[&str])] = &[
+    ("c", &["*.c", "*.h"]),
+    ("cpp", &["*.{cpp,cc,cxx,c++,hpp,hh,hxx,h++,h}"]),
+    ("csharp", &["*.cs"]),
+    ("css", &["*.{css,scss,sass,less}"]),
+    ("docker", &["Dockerfile", "*.dockerfile", "Dockerfile.*"]),
+    ("go", &["*.go"]),
+    ("html", &["*.{html,htm}"]),
+    ("java", &["*.java"]),
+    ("js", &["*.{js,mjs,cjs,jsx}"]),
+    ("json", &["*.json"]),
+    ("kotlin", &["*.{kt,kts}"]),
+    ("lua", &["*.lua"]),
+    ("make", &["Makefile", "makefile", "GNUmakefile", "*.mk"]),
+    ("markdown", &["*.{md,markdown}"]),
+    ("py", &["*.{py,pyi}"]),
+    ("ruby", &["*.rb", "Gemfile", "Rakefile"]),
+    ("rust", &["*.rs"]),
+    ("sh", &["*.{sh,bash,zsh}"]),
+    ("sql", &["*.sql"]),
+    ("swift", &["*.swift"]),
+    ("toml", &["*.toml"]),
+    ("ts", &["*.{ts,tsx,mts,cts}"]),
+    ("txt", &["*.txt"]),
+    ("web", &["*.{js,ts,jsx,tsx,css,html}"]),
+    ("xml", &["*.xml"]),
+    ("yaml", &["*.{yml,yaml}"]),
+];
+
+/// Registry of named file types (like ripgrep's --type)
+///
+/// Each type maps to one or more globs matched against the file name.
+#[derive(Debug, Clone)]
+pub struct TypeRegistry {
+    types: BTreeMap<String, Vec<String>>,
+}
+
+/// Compiled selection of file types to include and exclude
+#[derive(Debug, Clone)]
+pub struct TypeMatcher {
+    include: Option<Regex>,
+    exclude: Option<Regex>,
+}
+
+impl TypeRegistry {
+    /// Create a registry containing only the built-in types
+    pub fn new() -> Self {
+        let types = DEFAULT_TYPES
+            .iter()
+            .map(|(name, globs)| {
+                (
+                    name.to_string(),
+                    globs.iter().map(|g| g.to_string()).collect(),
+                )
+            })
+            .collect();
+
+        TypeRegistry { types }
+    }
+
+    /// Add a glob to a type from a `name:glob` definition
+    ///
+    /// Globs are appended if the type already exists, so built-in types
+    /// can be extended as well as new ones created.
+    pub fn add(&mut self, definition: &str) -> Result<()> {
+        let (name, glob) = definition.split_once(':').with_context(|| {
+            format!(
+                "invalid type definition '{}' (expected name:glob)",
+                definition
+            )
+        })?;
+
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+        {
+            bail!("invalid type name '{}'", name);
+        }
+        if glob.is_empty() {
+            bail!("empty glob in type definition '{}'", definition);
+        }
+        glob_to_regex(glob)?;
+
+        self.types
+            .entry(name.to_string())
+            .or_default()
+            .push(glob.to_string());
+        Ok(())
+    }
+
+    /// Iterate over all types and their globs in name order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &[String])> {
+        self.types
+            .iter()
+            .map(|(name, globs)| (name.as_str(), globs.as_slice()))
+    }
+
+    /// Compile the selected and negated types into a matcher
+    pub fn matcher(&self, selected: &[String], negated: &[String]) -> Result<TypeMatcher> {
+        Ok(TypeMatcher {
+            include: self.compile(selected)?,
+            exclude: self.compile(negated)?,
+        })
+    }
+
+    fn compile(&self, names: &[String]) -> Result<Option<Regex>> {
+        if names.is_empty() {
+            return Ok(None);
+        }
+
+        let mut alternatives = Vec::new();
+        for name in names {
+            let globs = self
+                .types
+                .get(name)
+                .with_context(|| format!("unrecognised file type '{}' (see --type-list)", name))?;
+            for glob in globs {
+                alternatives.push(glob_to_regex(glob)?);
+            }
+        }
+
+        let pattern = format!("^(?:{})$", alternatives.join("|"));
+        Regex::new(&pattern)
+            .map(Some)
+            .with_context(|| format!("compiling globs for types {}", names.join(", ")))
+    }
+}
+
+impl Default for TypeRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TypeMatcher {
+    /// Check whether a file name is selected and not negated
+    ///
+    /// Names are matched as bytes, so non-UTF-8 names are supported.
+    pub fn matches(&self, file_name: impl AsRef<OsStr>) -> bool {
+        let file_name = file_name.as_ref().as_encoded_bytes();
+
+        self.include
+            .as_ref()
+            .is_none_or(|re| re.is_match(file_name))
+            && !self
+                .exclude
+                .as_ref()
+                .is_some_and(|re| re.is_match(file_name))
+    }
+}
+
+/// Translate a file name glob into an (unanchored) regex
+///
+/// Supports `*`, `?`, `[...]` character classes and `{a,b}` alternation.
+/// Wildcards also match bytes that aren't valid UTF-8.
+fn glob_to_regex(glob: &str) -> Result<String> {
+    let mut regex = String::new();
+    let mut in_braces = false;
+    let mut chars = glob.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => regex.push_str("(?s-u:.)*"),
+            // Prefer a whole character, falling back to a single invalid byte
+            '?' => regex.push_str("(?s:.|(?-u:.))"),
+            '{' if !in_braces => {
+                in_braces = true;
+                regex.push_str("(?:");
+            }
+            '}' if in_braces => {
+                in_braces = false;
+                regex.push(')');
+            }
+            ',' if in_braces => regex.push('|'),
+            '[' => {
+                regex.push('[');
+                // A leading '!' negates the class, as '^' does in a regex
+                if chars.clone().next() == Some('!') {
+                    chars.next();
+                    regex.push('^');
+                }
+                push_class(&mut regex, &mut chars)
+                    .with_context(|| format!("unclosed '[' in glob '{}'", glob))?;
+                regex.push(']');
+            }
+            _ => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+
+    if in_braces {
+        regex.push(')');
+    }
+    Ok(regex)
+}
+
+/// Translate the members of a glob character class, up to and including
+/// its closing `]`, or None if it isn't closed
+///
+/// Every member is escaped, so regex class syntax (`^`, `&&`, `--`, `~~`,
+/// nested classes) is matched literally; only `a-z` ranges keep their
+/// meaning. A `]` first in the class is a literal, as in other globs.
+fn push_class(regex: &mut String, chars: &mut std::str::Chars) -> Option<()> {
+    let mut first = true;
+    loop {
+        let c = chars.next()?;
+        if c == ']' && !first {
+            return Some(());
+        }
+        first = false;
+        regex.push_str(&regex::escape(&c.to_string()));
+
+        let mut ahead = chars.clone();
+        if ahead.next() == Some('-')
+            && let Some(end) = ahead.next().filter(|&end| end != ']')
+        {
+            regex.push('-');
+            regex.push_str(&regex::escape(&end.to_string()));
+            *chars = ahead;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_translation() {
+        assert_eq!(glob_to_regex("*.rs").unwrap(), "(?s-u:.)*\\.rs");
+        assert_eq!(glob_to_regex("*.{js,ts}").unwrap(), "(?s-u:.)*\\.(?:js|ts)");
+        assert_eq!(
+            glob_to_regex("file?.[ch]").unwrap(),
+            "file(?s:.|(?-u:.))\\.[ch]"
+        );
+    }
+
+    #[test]
+    fn builtin_types_match() -> Result<()> {
+        let registry = TypeRegistry::new();
+
+        let rust = registry.matcher(&["rust".to_string()], &[])?;
+        assert!(rust.matches("main.rs"));
+        assert!(!rust.matches("main.rs.bak"));
+
+        let make = registry.matcher(&["make".to_string()], &[])?;
+        assert!(make.matches("Makefile"));
+        assert!(make.matches("rules.mk"));
+        assert!(!make.matches("Makefile.am"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn negated_types_exclude() -> Result<()> {
+        let registry = TypeRegistry::new();
+        let matcher = registry.matcher(&["web".to_string()], &["css".to_string()])?;
+
+        assert!(matcher.matches("app.tsx"));
+        assert!(!matcher.matches("style.css"));
+        assert!(!matcher.matches("main.rs"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn added_types_extend_registry() -> Result<()> {
+        let mut registry = TypeRegistry::new();
+        registry.add("proto:*.proto")?;
+        registry.add("rust:*.rs.in")?;
+
+        let matcher = registry.matcher(&["proto".to_string(), "rust".to_string()], &[])?;
+        assert!(matcher.matches("api.proto"));
+        assert!(matcher.matches("build.rs.in"));
+
+        assert!(registry.add("missing-glob").is_err());
+        assert!(registry.add("bad name:*.x").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn negated_bracket_classes() -> Result<()> {
+        assert_eq!(glob_to_regex("*.[!ch]")?, "(?s-u:.)*\\.[^ch]");
+
+        let mut registry = TypeRegistry::new();
+        registry.add("notc:*.[!ch]")?;
+        let matcher = registry.matcher(&["notc".to_string()], &[])?;
+        assert!(matcher.matches("main.o"));
+        assert!(!matcher.matches("main.c"));
+        assert!(!matcher.matches("main.h"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn bracket_classes_are_literal() -> Result<()> {
+        let mut registry = TypeRegistry::new();
+        registry.add("ops:[a^&~-]x")?;
+        registry.add("sets:[a&&b][+--][e~~f]")?;
+        registry.add("bracket:[]a]")?;
+        registry.add("notbracket:[!]]")?;
+        registry.add("range:[a-c]")?;
+        let matcher = |name: &str| registry.matcher(&[name.to_string()], &[]);
+
+        let ops = matcher("ops")?;
+        for name in ["ax", "^x", "&x", "~x", "-x"] {
+            assert!(ops.matches(name), "{}", name);
+        }
+        assert!(!ops.matches("bx"));
+
+        let sets = matcher("sets")?;
+        assert!(sets.matches("&,~"));
+        assert!(sets.matches("b-e"));
+        assert!(!sets.matches("xyz"));
+
+        assert!(matcher("bracket")?.matches("]"));
+        assert!(matcher("bracket")?.matches("a"));
+        assert!(!matcher("notbracket")?.matches("]"));
+        assert!(matcher("notbracket")?.matches("x"));
+        assert!(matcher("range")?.matches("b"));
+        assert!(!matcher("range")?.matches("-"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn unclosed_bracket_is_rejected() {
+        let mut registry = TypeRegistry::new();
+        for definition in ["bad:*.[ch", "bad:[]", "bad:[!", "bad:x["] {
+            assert!(registry.add(definition).is_err(), "{}", definition);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_names_match() -> Result<()> {
+        use std::os::unix::ffi::OsStrExt;
+
+        let mut registry = TypeRegistry::new();
+        registry.add("latin:caf?.txt")?;
+        let name = OsStr::from_bytes(b"caf\xe9.txt");
+
+        assert!(registry.matcher(&["txt".to_string()], &[])?.matches(name));
+        assert!(registry.matcher(&["latin".to_string()], &[])?.matches(name));
+        assert!(!registry.matcher(&[], &["txt".to_string()])?.matches(name));
+
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_type_is_error() {
+        let registry = TypeRegistry::new();
+        assert!(registry.matcher(&["nope".to_string()], &[]).is_err());
+    }
+}
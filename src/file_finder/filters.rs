@@ -0,0 +1,347 @@
+use anyhow::{Context, Result, bail};
+use std::fs::Metadata;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub(crate) const SECONDS_PER_DAY: u64 = 86_400;
+
+/// Predicate applied to a file's metadata (like find's -size, -mtime, -perm)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MetadataFilter {
+    /// File is strictly larger than the given number of bytes (`+10M`)
+    LargerThan(u64),
+    /// File is strictly smaller than the given number of bytes (`-1k`)
+    SmallerThan(u64),
+    /// File is exactly the given number of bytes (`512`)
+    SizeEquals(u64),
+    /// File was modified after the given time
+    ModifiedAfter(SystemTime),
+    /// File was modified before the given time
+    ModifiedBefore(SystemTime),
+    /// Permission bits match the given octal mode
+    Permissions(PermissionMatch, u32),
+    /// File is executable by someone (any execute bit set)
+    Executable,
+}
+
+/// How a permission mode is compared, following find's -perm semantics
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PermissionMatch {
+    /// Mode bits are exactly equal (`644`)
+    Exact,
+    /// All of the given bits are set (`-644`)
+    All,
+    /// Any of the given bits are set (`/111`)
+    Any,
+}
+
+impl MetadataFilter {
+    /// Parse a find-style size specification
+    ///
+    /// A leading `+` means "larger than", a leading `-` means "smaller than"
+    /// and no prefix means "exactly". Units are powers of 1024: `b` (bytes,
+    /// the default), `k`, `M`, `G` and `T`.
+    pub fn parse_size(spec: &str) -> Result<MetadataFilter> {
+        let (constructor, rest): (fn(u64) -> MetadataFilter, &str) =
+            if let Some(rest) = spec.strip_prefix('+') {
+                (MetadataFilter::LargerThan, rest)
+            } else if let Some(rest) = spec.strip_prefix('-') {
+                (MetadataFilter::SmallerThan, rest)
+            } else {
+                (MetadataFilter::SizeEquals, spec)
+            };
+
+        let split = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let (number, unit) = rest.split_at(split);
+
+        let number: u64 = number
+            .parse()
+            .with_context(|| format!("invalid size '{}'", spec))?;
+        let multiplier: u64 = match unit.to_ascii_lowercase().as_str() {
+            "" | "b" | "c" => 1,
+            "k" => 1 << 10,
+            "m" => 1 << 20,
+            "g" => 1 << 30,
+            "t" => 1 << 40,
+            _ => bail!("invalid size unit '{}' in '{}'", unit, spec),
+        };
+
+        Ok(constructor(number.saturating_mul(multiplier)))
+    }
+
+    /// Files modified within the given duration or since the given date
+    pub fn parse_changed_within(spec: &str) -> Result<MetadataFilter> {
+        Ok(MetadataFilter::ModifiedAfter(parse_time_bound(
+            spec,
+            SystemTime::now(),
+        )?))
+    }
+
+    /// Files modified longer ago than the given duration or before the given date
+    pub fn parse_changed_before(spec: &str) -> Result<MetadataFilter> {
+        Ok(MetadataFilter::ModifiedBefore(parse_time_bound(
+            spec,
+            SystemTime::now(),
+        )?))
+    }
+
+    /// Files modified more recently than the reference file
+    pub fn newer_than(reference: &Path) -> Result<MetadataFilter> {
+        let modified = reference
+            .metadata()
+            .and_then(|m| m.modified())
+            .with_context(|| format!("reading modification time of '{}'", reference.display()))?;
+
+        Ok(MetadataFilter::ModifiedAfter(modified))
+    }
+
+    /// Parse a find-style octal permission mode (`644`, `-644` or `/111`)
+    pub fn parse_permissions(spec: &str) -> Result<MetadataFilter> {
+        let (kind, mode) = if let Some(mode) = spec.strip_prefix('-') {
+            (PermissionMatch::All, mode)
+        } else if let Some(mode) = spec.strip_prefix('/') {
+            (PermissionMatch::Any, mode)
+        } else {
+            (PermissionMatch::Exact, spec)
+        };
+
+        // from_str_radix would also accept a sign
+        if mode.is_empty() || !mode.bytes().all(|b| b.is_ascii_digit()) {
+            bail!("invalid octal permission mode '{}'", spec);
+        }
+        let mode = u32::from_str_radix(mode, 8)
+            .with_context(|| format!("invalid octal permission mode '{}'", spec))?;
+        if mode > 0o7777 {
+            bail!("permission mode out of range '{}'", spec);
+        }
+
+        Ok(MetadataFilter::Permissions(kind, mode))
+    }
+
+    /// Check whether a file's metadata satisfies this filter
+    pub fn matches(&self, metadata: &Metadata) -> bool {
+        match self {
+            MetadataFilter::LargerThan(size) => metadata.len() > *size,
+            MetadataFilter::SmallerThan(size) => metadata.len() < *size,
+            MetadataFilter::SizeEquals(size) => metadata.len() == *size,
+            MetadataFilter::ModifiedAfter(time) => {
+                metadata.modified().is_ok_and(|modified| modified > *time)
+            }
+            MetadataFilter::ModifiedBefore(time) => {
+                metadata.modified().is_ok_and(|modified| modified < *time)
+            }
+            MetadataFilter::Permissions(kind, mode) => match file_mode(metadata) {
+                Some(actual) => match kind {
+                    PermissionMatch::Exact => actual & 0o7777 == *mode,
+                    PermissionMatch::All => actual & mode == *mode,
+                    PermissionMatch::Any => *mode == 0 || actual & mode != 0,
+                },
+                None => false,
+            },
+            MetadataFilter::Executable => file_mode(metadata).is_some_and(|mode| mode & 0o111 != 0),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn file_mode(metadata: &Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(metadata.permissions().mode())
+}
+
+#[cfg(not(unix))]
+fn file_mode(_metadata: &Metadata) -> Option<u32> {
+    // Permission bits are a Unix concept; nothing matches elsewhere
+    None
+}
+
+/// Resolve either a relative duration (`2d`, `3h`, `1w`) or an absolute
+/// date (`2026-01-01` or `2026-01-01 12:30:00`, UTC) into a point in time
+fn parse_time_bound(spec: &str, now: SystemTime) -> Result<SystemTime> {
+    if let Some(duration) = parse_duration(spec) {
+        return now
+            .checked_sub(duration)
+            .with_context(|| format!("duration out of range '{}'", spec));
+    }
+
+    parse_date(spec).with_context(|| {
+        format!(
+            "invalid time '{}' (expected a duration like '2d' or a date like '2026-01-01')",
+            spec
+        )
+    })
+}
+
+fn parse_duration(spec: &str) -> Option<Duration> {
+    let split = spec.find(|c: char| !c.is_ascii_digit())?;
+    let (number, unit) = spec.split_at(split);
+    let number: u64 = number.parse().ok()?;
+
+    let seconds = match unit {
+        "s" | "sec" | "secs" => 1,
+        "m" | "min" | "mins" => 60,
+        "h" | "hour" | "hours" => 3_600,
+        "d" | "day" | "days" => SECONDS_PER_DAY,
+        "w" | "week" | "weeks" => 7 * SECONDS_PER_DAY,
+        _ => return None,
+    };
+
+    Some(Duration::from_secs(number.checked_mul(seconds)?))
+}
+
+fn parse_date(spec: &str) -> Result<SystemTime> {
+    let (date, time) = match spec.split_once([' ', 'T']) {
+        Some((date, time)) => (date, Some(time)),
+        None => (spec, None),
+    };
+
+    let mut date_parts = date.splitn(3, '-');
+    let year: i64 = date_parts.next().unwrap_or_default().parse()?;
+    let month: u32 = date_parts.next().context("missing month")?.parse()?;
+    let day: u32 = date_parts.next().context("missing day")?.parse()?;
+    if !(1..=12).contains(&month) || !(1..=days_in_month(year, month)).contains(&day) {
+        bail!("date out of range");
+    }
+
+    let mut seconds_of_day = 0;
+    if let Some(time) = time {
+        let mut time_parts = time.splitn(3, ':');
+        let hour: u64 = time_parts.next().unwrap_or_default().parse()?;
+        let minute: u64 = time_parts.next().unwrap_or("0").parse()?;
+        let second: u64 = time_parts.next().unwrap_or("0").parse()?;
+        if hour > 23 || minute > 59 || second > 59 {
+            bail!("time out of range");
+        }
+        seconds_of_day = hour * 3_600 + minute * 60 + second;
+    }
+
+    let days = days_from_civil(year, month, day);
+    if days < 0 {
+        bail!("dates before 1970 are not supported");
+    }
+
+    Ok(UNIX_EPOCH + Duration::from_secs(days as u64 * SECONDS_PER_DAY + seconds_of_day))
+}
+
+/// Number of days in a month of the Gregorian calendar
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date
+/// (Howard Hinnant's days_from_civil algorithm)
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_size_specs() -> Result<()> {
+        assert_eq!(
+            MetadataFilter::parse_size("+10M")?,
+            MetadataFilter::LargerThan(10 * 1024 * 1024)
+        );
+        assert_eq!(
+            MetadataFilter::parse_size("-1k")?,
+            MetadataFilter::SmallerThan(1024)
+        );
+        assert_eq!(
+            MetadataFilter::parse_size("512")?,
+            MetadataFilter::SizeEquals(512)
+        );
+        assert!(MetadataFilter::parse_size("+10X").is_err());
+        assert!(MetadataFilter::parse_size("big").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_durations_and_dates() -> Result<()> {
+        let now = UNIX_EPOCH + Duration::from_secs(10 * SECONDS_PER_DAY);
+
+        assert_eq!(
+            parse_time_bound("2d", now)?,
+            UNIX_EPOCH + Duration::from_secs(8 * SECONDS_PER_DAY)
+        );
+        assert_eq!(
+            parse_time_bound("1970-01-02", now)?,
+            UNIX_EPOCH + Duration::from_secs(SECONDS_PER_DAY)
+        );
+        assert_eq!(
+            parse_time_bound("2026-01-01 00:00:01", now)?,
+            UNIX_EPOCH + Duration::from_secs(1_767_225_601)
+        );
+        assert!(parse_time_bound("yesterday", now).is_err());
+        assert!(parse_time_bound("2026-13-01", now).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn reject_days_past_end_of_month() -> Result<()> {
+        let now = UNIX_EPOCH;
+
+        assert!(parse_time_bound("2026-02-31", now).is_err());
+        assert!(parse_time_bound("2026-02-29", now).is_err());
+        assert!(parse_time_bound("2026-04-31", now).is_err());
+        assert!(parse_time_bound("2100-02-29", now).is_err());
+        assert!(parse_time_bound("2028-02-29", now).is_ok());
+        assert!(parse_time_bound("2000-02-29", now).is_ok());
+        assert!(parse_time_bound("2026-12-31", now).is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_permission_modes() -> Result<()> {
+        assert_eq!(
+            MetadataFilter::parse_permissions("644")?,
+            MetadataFilter::Permissions(PermissionMatch::Exact, 0o644)
+        );
+        assert_eq!(
+            MetadataFilter::parse_permissions("-600")?,
+            MetadataFilter::Permissions(PermissionMatch::All, 0o600)
+        );
+        assert_eq!(
+            MetadataFilter::parse_permissions("/111")?,
+            MetadataFilter::Permissions(PermissionMatch::Any, 0o111)
+        );
+        assert!(MetadataFilter::parse_permissions("999").is_err());
+        for spec in ["+644", "-+644", "/+111", "", "-"] {
+            assert!(MetadataFilter::parse_permissions(spec).is_err(), "{}", spec);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn size_filters_match_metadata() -> Result<()> {
+        let metadata = std::fs::metadata("Cargo.toml")?;
+        let len = metadata.len();
+
+        assert!(MetadataFilter::LargerThan(len - 1).matches(&metadata));
+        assert!(!MetadataFilter::LargerThan(len).matches(&metadata));
+        assert!(MetadataFilter::SmallerThan(len + 1).matches(&metadata));
+        assert!(MetadataFilter::SizeEquals(len).matches(&metadata));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,113 @@
+use anyhow::{Error, Result, bail};
+use std::fs::{self, FileType};
+use std::path::Path;
+use std::str::FromStr;
+
+/// Kind of directory entry to return (like fd's --type)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    /// Regular files (`f`), the default
+    File,
+    /// Directories (`d`), not including the roots themselves
+    Directory,
+    /// Symbolic links (`l`), whether or not they're followed or broken
+    Symlink,
+    /// Empty regular files and empty directories (`e`)
+    Empty,
+    /// Unix domain sockets (`s`)
+    Socket,
+    /// Named pipes / FIFOs (`p`)
+    Pipe,
+}
+
+impl FromStr for EntryKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "f" | "file" => Ok(EntryKind::File),
+            "d" | "dir" | "directory" => Ok(EntryKind::Directory),
+            "l" | "symlink" => Ok(EntryKind::Symlink),
+            "e" | "empty" => Ok(EntryKind::Empty),
+            "s" | "socket" => Ok(EntryKind::Socket),
+            "p" | "pipe" => Ok(EntryKind::Pipe),
+            _ => bail!("unknown entry kind '{}' (expected f, d, l, e, s or p)", s),
+        }
+    }
+}
+
+impl EntryKind {
+    /// Check whether an entry is of this kind
+    ///
+    /// `file_type` is the type of the link target for followed symlinks,
+    /// and of the entry itself otherwise; `is_symlink` is whether the
+    /// entry itself is a link.
+    pub(super) fn matches(self, path: &Path, file_type: FileType, is_symlink: bool) -> bool {
+        match self {
+            EntryKind::File => file_type.is_file(),
+            EntryKind::Directory => file_type.is_dir(),
+            EntryKind::Symlink => is_symlink,
+            EntryKind::Empty if file_type.is_file() => {
+                fs::metadata(path).is_ok_and(|metadata| metadata.len() == 0)
+            }
+            EntryKind::Empty if file_type.is_dir() => {
+                fs::read_dir(path).is_ok_and(|mut entries| entries.next().is_none())
+            }
+            EntryKind::Empty => false,
+            EntryKind::Socket => is_socket(file_type),
+            EntryKind::Pipe => is_pipe(file_type),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn is_socket(file_type: FileType) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    file_type.is_socket()
+}
+
+#[cfg(not(unix))]
+fn is_socket(_file_type: FileType) -> bool {
+    false
+}
+
+#[cfg(unix)]
+fn is_pipe(file_type: FileType) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    file_type.is_fifo()
+}
+
+#[cfg(not(unix))]
+fn is_pipe(_file_type: FileType) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_entry_kinds() -> Result<()> {
+        assert_eq!("f".parse::<EntryKind>()?, EntryKind::File);
+        assert_eq!("dir".parse::<EntryKind>()?, EntryKind::Directory);
+        assert_eq!("l".parse::<EntryKind>()?, EntryKind::Symlink);
+        assert_eq!("e".parse::<EntryKind>()?, EntryKind::Empty);
+        assert!("x".parse::<EntryKind>().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn kinds_match_entries() -> Result<()> {
+        let cargo_toml = fs::metadata("Cargo.toml")?.file_type();
+        let src = fs::metadata("src")?.file_type();
+
+        assert!(EntryKind::File.matches(Path::new("Cargo.toml"), cargo_toml, false));
+        assert!(!EntryKind::Directory.matches(Path::new("Cargo.toml"), cargo_toml, false));
+        assert!(EntryKind::Directory.matches(Path::new("src"), src, false));
+        assert!(!EntryKind::Empty.matches(Path::new("src"), src, false));
+        assert!(EntryKind::Symlink.matches(Path::new("src"), src, true));
+
+        Ok(())
+    }
+}
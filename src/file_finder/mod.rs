@@ -1,68 +1,564 @@
 use anyhow::{Context, Result};
+use rayon::{ThreadPool, ThreadPoolBuildError, ThreadPoolBuilder};
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::fs::{self, FileType, Metadata};
+use std::io::{self, ErrorKind};
 use std::path::{Path, PathBuf};
-use walkdir::{IntoIter, WalkDir};
+use std::sync::mpsc::{self, sync_channel};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use walkdir::{DirEntry, WalkDir};
+mod file_list;
+mod file_types;
+mod filters;
+mod kind;
+mod parallel;
 mod path_parser;
 
+pub use file_list::read_file_list;
+pub use file_types::{TypeMatcher, TypeRegistry};
+pub(crate) use filters::SECONDS_PER_DAY;
+pub use filters::{MetadataFilter, PermissionMatch};
+pub use kind::EntryKind;
+
+/// Number of discovered paths buffered between the walker and its consumer
+const STREAM_CAPACITY: usize = 1024;
+
+/// Paths streamed from a running traversal (see `Finder::stream`)
+pub type PathStream = mpsc::IntoIter<PathBuf>;
+
 pub struct Finder<'a> {
-    path: &'a Path,
+    roots: Vec<&'a Path>,
+    files: Option<Vec<PathBuf>>,
+    kinds: Vec<EntryKind>,
+    filters: Vec<MetadataFilter>,
+    types: Option<TypeMatcher>,
+    min_depth: Option<usize>,
+    max_depth: Option<usize>,
+    hidden: bool,
+    follow_links: bool,
+    same_file_system: bool,
+    threads: usize,
+    /// Pool for the parallel walker, built on first use and then reused
+    walk_pool: OnceLock<Result<ThreadPool, ThreadPoolBuildError>>,
 }
 
-impl Finder<'_> {
+impl<'a> Finder<'a> {
     pub fn new(root: Option<&str>) -> Result<Finder<'_>> {
-        let path = path_parser::parse(root).context("validating root path")?;
+        Finder::with_roots(root)
+    }
+
+    /// Create a finder over several root paths, defaulting to CWD if none are given
+    ///
+    /// Results from all roots are merged; roots that overlap are only walked once.
+    pub fn with_roots(roots: impl IntoIterator<Item = &'a str>) -> Result<Finder<'a>> {
+        let mut paths = Vec::new();
+        for root in roots {
+            paths.push(path_parser::parse(Some(root)).context("validating root path")?);
+        }
+        if paths.is_empty() {
+            paths.push(path_parser::parse(None).context("validating root path")?);
+        }
+
+        Ok(Finder {
+            roots: paths,
+            files: None,
+            kinds: vec![EntryKind::File],
+            filters: Vec::new(),
+            types: None,
+            min_depth: None,
+            max_depth: None,
+            hidden: false,
+            follow_links: true,
+            same_file_system: false,
+            threads: 0,
+            walk_pool: OnceLock::new(),
+        })
+    }
 
-        Ok(Finder { path })
+    /// Create a finder over an explicit list of files instead of walking directories
+    ///
+    /// Name, type, kind and metadata filters still apply; traversal options
+    /// such as depth limits and hidden-file handling don't.
+    pub fn with_files(files: Vec<PathBuf>) -> Finder<'a> {
+        Finder {
+            roots: Vec::new(),
+            files: Some(files),
+            kinds: vec![EntryKind::File],
+            filters: Vec::new(),
+            types: None,
+            min_depth: None,
+            max_depth: None,
+            hidden: false,
+            follow_links: true,
+            same_file_system: false,
+            threads: 0,
+            walk_pool: OnceLock::new(),
+        }
     }
 
+    /// Return entries of any of the given kinds (regular files by default)
+    ///
+    /// Every kind is combined with the name, type and metadata filters.
+    /// An empty list keeps the default.
+    pub fn kinds(mut self, kinds: Vec<EntryKind>) -> Finder<'a> {
+        if !kinds.is_empty() {
+            self.kinds = kinds;
+        }
+        self
+    }
+
+    /// Only return files whose metadata satisfies every given filter
+    pub fn filters(mut self, filters: Vec<MetadataFilter>) -> Finder<'a> {
+        self.filters = filters;
+        self
+    }
+
+    /// Only return files whose name matches the given file type selection
+    pub fn types(mut self, types: TypeMatcher) -> Finder<'a> {
+        self.types = Some(types);
+        self
+    }
+
+    /// Skip entries shallower than `depth` (files directly in the root are depth 1)
+    pub fn min_depth(mut self, depth: Option<usize>) -> Finder<'a> {
+        self.min_depth = depth;
+        self
+    }
+
+    /// Don't descend deeper than `depth` (files directly in the root are depth 1)
+    pub fn max_depth(mut self, depth: Option<usize>) -> Finder<'a> {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Include hidden (dot) files and directories, except `.git`
+    ///
+    /// The root itself is always searched, so `.git` can still be
+    /// searched explicitly by passing it as the root path.
+    pub fn hidden(mut self, hidden: bool) -> Finder<'a> {
+        self.hidden = hidden;
+        self
+    }
+
+    /// Follow symbolic links (the default)
+    ///
+    /// When following links, each target file is returned once even if
+    /// several links (or hard links) point at it.
+    pub fn follow_links(mut self, follow_links: bool) -> Finder<'a> {
+        self.follow_links = follow_links;
+        self
+    }
+
+    /// Don't cross file system boundaries (mount points) below each root
+    pub fn same_file_system(mut self, same_file_system: bool) -> Finder<'a> {
+        self.same_file_system = same_file_system;
+        self
+    }
+
+    /// Number of threads used to walk directories (0 = auto-detect, 1 = sequential)
+    pub fn threads(mut self, threads: usize) -> Finder<'a> {
+        self.threads = threads;
+        self.walk_pool = OnceLock::new();
+        self
+    }
+
+    /// Find all matching files
+    ///
+    /// Files are returned in the order the walk finds them. Directories are
+    /// walked in parallel unless `threads(1)` is set, so that order varies
+    /// between runs; sort the paths (e.g. with `sort::sort_paths`) when a
+    /// stable order is needed.
     pub fn find(&self, query: Option<&str>, verbose: bool) -> Vec<PathBuf> {
-        // Common file metadata handling for all cases
-        let file_iterator = self.find_internal().filter_map(|e| e.ok()).filter_map(|e| {
-            match e.metadata() {
-                Ok(metadata) if metadata.is_file() => Some(e),
-                Ok(_) => None, // Not a file (directory, symlink, etc.)
+        let results = Mutex::new(Vec::new());
+        self.walk(query, verbose, &|path| results.lock().unwrap().push(path));
+        results.into_inner().unwrap()
+    }
+
+    /// Stream matching files to `consume` while the tree is still being walked
+    ///
+    /// Traversal runs on a background thread and feeds a bounded channel, so
+    /// the consumer sees the first paths immediately and memory use doesn't
+    /// grow with the number of files. The result of `consume` is returned
+    /// once both sides have finished.
+    pub fn stream<R>(
+        &self,
+        query: Option<&str>,
+        verbose: bool,
+        consume: impl FnOnce(PathStream) -> R,
+    ) -> R {
+        let (sender, receiver) = sync_channel(STREAM_CAPACITY);
+
+        thread::scope(|scope| {
+            scope.spawn(move || {
+                // Sending only fails once the consumer has stopped listening
+                self.walk(query, verbose, &|path| {
+                    let _ = sender.send(path);
+                });
+            });
+
+            consume(receiver.into_iter())
+        })
+    }
+
+    /// Pass every matching file to `sink`, using the parallel walker unless
+    /// a single thread was requested
+    fn walk(&self, query: Option<&str>, verbose: bool, sink: &(dyn Fn(PathBuf) + Sync)) {
+        if let Some(files) = &self.files {
+            return self.select_listed(files, query, verbose, sink);
+        }
+
+        // Mount points can only be detected by the parallel walker on Unix
+        if self.threads == 1 || (cfg!(not(unix)) && self.same_file_system) {
+            return self.walk_sequential(query, verbose, sink);
+        }
+
+        match self.walk_pool() {
+            Ok(pool) => parallel::walk(self, pool, query, verbose, sink),
+            Err(err) => {
+                if verbose {
+                    eprintln!(
+                        "Warning: Falling back to sequential traversal: creating directory walker thread pool: {}",
+                        err
+                    );
+                }
+                self.walk_sequential(query, verbose, sink);
+            }
+        }
+    }
+
+    /// The parallel walker's pool, sized by `threads`
+    fn walk_pool(&self) -> &Result<ThreadPool, ThreadPoolBuildError> {
+        self.walk_pool.get_or_init(|| {
+            ThreadPoolBuilder::new()
+                .num_threads(self.threads)
+                .thread_name(|i| format!("finders-walk-{}", i))
+                .build()
+        })
+    }
+
+    /// Filter an explicit file list, in the order given
+    fn select_listed(
+        &self,
+        files: &[PathBuf],
+        query: Option<&str>,
+        verbose: bool,
+        sink: &dyn Fn(PathBuf),
+    ) {
+        let mut seen = HashSet::new();
+
+        for path in files {
+            let metadata = match self.entry_metadata(path) {
+                Ok(metadata) => metadata,
+                Err(err) => {
+                    if verbose {
+                        eprintln!("Warning: Cannot read: {} ({})", path.display(), err);
+                    }
+                    continue;
+                }
+            };
+            let is_symlink = path.symlink_metadata().is_ok_and(|m| m.is_symlink());
+
+            let name = path.file_name().unwrap_or(path.as_os_str());
+            if self.matches_kind(path, metadata.file_type(), is_symlink)
+                && self.matches_file_name(name, query)
+                && self.matches_metadata(&metadata)
+                && seen.insert(self.entry_id(path, &metadata, is_symlink))
+            {
+                sink(path.clone());
+            }
+        }
+    }
+
+    /// Walk each root in turn on the current thread
+    fn walk_sequential(&self, query: Option<&str>, verbose: bool, sink: &dyn Fn(PathBuf)) {
+        // Identities of files already returned, so link targets and files
+        // reachable from several roots are only searched once
+        let mut seen = HashSet::new();
+        let dedupe = self.needs_dedupe();
+
+        for entry in self.find_internal(verbose) {
+            let (path, file_type, is_symlink) = match entry {
+                // The roots themselves are never returned as directories
+                Ok(e) if e.depth() == 0 && e.file_type().is_dir() => continue,
+                Ok(e) => {
+                    let is_symlink = e.path_is_symlink();
+                    if verbose && is_symlink && !self.follow_links && e.path().metadata().is_err() {
+                        eprintln!("Warning: Broken symlink: {}", e.path().display());
+                    }
+                    let file_type = e.file_type();
+                    (e.into_path(), file_type, is_symlink)
+                }
+                Err(err) => {
+                    if verbose {
+                        report_walk_error(&err);
+                    }
+                    // Links that can't be followed are still symlinks
+                    match broken_symlink(&err) {
+                        Some((path, file_type)) if err.depth() >= self.min_depth.unwrap_or(0) => {
+                            (path, file_type, true)
+                        }
+                        _ => continue,
+                    }
+                }
+            };
+
+            // Apply kind, filename and file type filters first, as they rarely need a stat call
+            let name = path.file_name().unwrap_or(path.as_os_str());
+            if !self.matches_kind(&path, file_type, is_symlink)
+                || !self.matches_file_name(name, query)
+            {
+                continue;
+            }
+
+            match self.entry_metadata(&path) {
+                Ok(metadata) => {
+                    if self.matches_metadata(&metadata)
+                        && (!dedupe || seen.insert(self.entry_id(&path, &metadata, is_symlink)))
+                    {
+                        sink(path);
+                    }
+                }
                 Err(err) => {
                     if verbose {
                         eprintln!(
                             "Warning: Cannot read metadata: {} ({})",
-                            e.path().display(),
+                            path.display(),
                             err
                         );
                     }
-                    None
                 }
             }
-        });
-
-        // Apply filename filter if provided, otherwise return all files
-        if let Some(pattern) = query {
-            file_iterator
-                .filter_map(|e| {
-                    match e.file_name().to_str() {
-                        Some(name) if name.contains(pattern) => Some(e),
-                        Some(_) => None, // Filename doesn't match pattern
-                        None => {
-                            if verbose {
-                                eprintln!(
-                                    "Warning: Skipping file with non-UTF8 name: {}",
-                                    e.path().display()
-                                );
-                            }
-                            None
-                        }
-                    }
-                })
-                .map(|e| e.into_path())
-                .collect()
+        }
+    }
+
+    /// Check the entry against the requested kinds
+    fn matches_kind(&self, path: &Path, file_type: FileType, is_symlink: bool) -> bool {
+        self.kinds
+            .iter()
+            .any(|kind| kind.matches(path, file_type, is_symlink))
+    }
+
+    /// Identity used to return each entry only once
+    ///
+    /// When listing symlinks, links are identified by themselves rather than
+    /// their targets, so several links to one file are all returned.
+    fn entry_id(&self, path: &Path, metadata: &Metadata, is_symlink: bool) -> FileId {
+        if is_symlink
+            && self.kinds.contains(&EntryKind::Symlink)
+            && let Ok(link) = path.symlink_metadata()
+        {
+            return file_id(path, &link);
+        }
+        file_id(path, metadata)
+    }
+
+    /// Metadata of an entry's link target when following links, falling back
+    /// to the link itself for broken links
+    fn entry_metadata(&self, path: &Path) -> io::Result<Metadata> {
+        if self.follow_links {
+            fs::metadata(path).or_else(|_| fs::symlink_metadata(path))
         } else {
-            file_iterator.map(|e| e.into_path()).collect()
+            fs::symlink_metadata(path)
+        }
+    }
+
+    /// Apply the filename pattern and file type filters
+    ///
+    /// Names are compared as bytes, so files whose names aren't valid UTF-8
+    /// can still be matched.
+    fn matches_file_name(&self, name: &OsStr, query: Option<&str>) -> bool {
+        query.is_none_or(|pattern| contains_bytes(name.as_encoded_bytes(), pattern.as_bytes()))
+            && self.types.as_ref().is_none_or(|types| types.matches(name))
+    }
+
+    /// Apply the metadata filters
+    fn matches_metadata(&self, metadata: &Metadata) -> bool {
+        self.filters.iter().all(|filter| filter.matches(metadata))
+    }
+
+    /// Whether the same file may be reached through more than one path
+    fn needs_dedupe(&self) -> bool {
+        self.follow_links || self.roots.len() > 1
+    }
+
+    /// Whether traversal should enter (or return) an entry with this name
+    fn is_visible(&self, name: &OsStr, depth: usize) -> bool {
+        if depth == 0 {
+            return true;
+        }
+
+        if name == ".git" {
+            return false;
+        }
+        self.hidden || !name.as_encoded_bytes().starts_with(b".")
+    }
+
+    /// Roots to walk, without duplicates
+    fn distinct_roots(&self) -> Vec<&'a Path> {
+        path_parser::distinct_roots(&self.roots, |outer, nested| self.reaches(outer, nested))
+    }
+
+    /// Whether walking `outer` enters `nested`, a directory inside it, making
+    /// `nested` redundant as a root
+    fn reaches(&self, outer: &Path, nested: &Path) -> bool {
+        // Depth limits apply relative to each root, so they differ between them
+        if self.min_depth.is_some() || self.max_depth.is_some() {
+            return false;
+        }
+
+        let Ok(relative) = nested.strip_prefix(outer) else {
+            return false;
+        };
+        if !relative
+            .iter()
+            .all(|component| self.is_visible(component, 1))
+        {
+            return false;
+        }
+
+        #[cfg(unix)]
+        if self.same_file_system {
+            use std::os::unix::fs::MetadataExt;
+
+            // Every directory on the way down must share the outer root's device
+            let device = |path: &Path| path.metadata().ok().map(|m| m.dev());
+            let root_device = device(outer);
+            if nested
+                .ancestors()
+                .take_while(|dir| *dir != outer)
+                .any(|dir| root_device.is_none() || device(dir) != root_device)
+            {
+                return false;
+            }
+        }
+        #[cfg(not(unix))]
+        if self.same_file_system {
+            return false;
+        }
+
+        true
+    }
+
+    /// Whether a directory lives on a different device than its root (Unix only)
+    #[cfg(unix)]
+    fn is_mount_point(&self, entry: &DirEntry, root_device: Option<u64>, verbose: bool) -> bool {
+        use std::os::unix::fs::MetadataExt;
+
+        if !self.same_file_system || entry.depth() == 0 || !entry.file_type().is_dir() {
+            return false;
+        }
+
+        let crosses = match (root_device, entry.metadata()) {
+            (Some(root), Ok(metadata)) => metadata.dev() != root,
+            _ => false,
+        };
+        if crosses && verbose {
+            eprintln!("Warning: Skipping mount point: {}", entry.path().display());
         }
+        crosses
+    }
+
+    fn find_internal(&self, verbose: bool) -> impl Iterator<Item = walkdir::Result<DirEntry>> + '_ {
+        self.distinct_roots().into_iter().flat_map(move |root| {
+            let mut walker = WalkDir::new(root).follow_links(self.follow_links);
+            if let Some(depth) = self.min_depth {
+                walker = walker.min_depth(depth);
+            }
+            if let Some(depth) = self.max_depth {
+                walker = walker.max_depth(depth);
+            }
+
+            // Mount points are detected here so they can be reported;
+            // elsewhere walkdir skips them silently
+            #[cfg(unix)]
+            let root_device = {
+                use std::os::unix::fs::MetadataExt;
+                root.metadata().ok().map(|m| m.dev())
+            };
+            #[cfg(not(unix))]
+            {
+                walker = walker.same_file_system(self.same_file_system);
+            }
+
+            walker.into_iter().filter_entry(move |entry| {
+                #[cfg(unix)]
+                if self.is_mount_point(entry, root_device, verbose) {
+                    return false;
+                }
+                self.is_visible(entry.file_name(), entry.depth())
+            })
+        })
     }
+}
 
-    fn find_internal(&self) -> IntoIter {
-        WalkDir::new(self.path).follow_links(true).into_iter()
+/// Check whether `needle` occurs anywhere in `haystack`
+fn contains_bytes(haystack: &[u8], needle: &[u8]) -> bool {
+    needle.is_empty()
+        || haystack
+            .windows(needle.len())
+            .any(|window| window == needle)
+}
+
+/// Explain a traversal error (symlink loops, broken links, unreadable directories)
+fn report_walk_error(err: &walkdir::Error) {
+    let path = err
+        .path()
+        .map(|p| p.display().to_string())
+        .unwrap_or_default();
+
+    if let Some(ancestor) = err.loop_ancestor() {
+        eprintln!(
+            "Warning: Symlink loop: {} points to ancestor {}",
+            path,
+            ancestor.display()
+        );
+    } else if err
+        .io_error()
+        .is_some_and(|e| e.kind() == ErrorKind::NotFound)
+        && err
+            .path()
+            .is_some_and(|p| p.symlink_metadata().is_ok_and(|m| m.is_symlink()))
+    {
+        eprintln!("Warning: Broken symlink: {}", path);
+    } else {
+        eprintln!("Warning: Cannot read: {} ({})", path, err);
     }
 }
 
+/// The path and type of a broken symlink behind a traversal error
+fn broken_symlink(err: &walkdir::Error) -> Option<(PathBuf, FileType)> {
+    if err.io_error()?.kind() != ErrorKind::NotFound {
+        return None;
+    }
+    let path = err.path()?;
+    let metadata = path.symlink_metadata().ok()?;
+    metadata
+        .is_symlink()
+        .then(|| (path.to_path_buf(), metadata.file_type()))
+}
+
+/// Identity of a file on disk, shared by every path (or link) that reaches it
+#[derive(Debug, PartialEq, Eq, Hash)]
+enum FileId {
+    #[cfg(unix)]
+    Inode(u64, u64),
+    #[cfg(not(unix))]
+    Path(PathBuf),
+}
+
+#[cfg(unix)]
+fn file_id(_path: &Path, metadata: &Metadata) -> FileId {
+    use std::os::unix::fs::MetadataExt;
+    FileId::Inode(metadata.dev(), metadata.ino())
+}
+
+#[cfg(not(unix))]
+fn file_id(path: &Path, _metadata: &Metadata) -> FileId {
+    FileId::Path(path.canonicalize().unwrap_or_else(|_| path.to_path_buf()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -77,7 +573,7 @@ mod tests {
 
         let expected_finder_path = Path::new(cwd);
 
-        assert_eq!(finder.path, expected_finder_path);
+        assert_eq!(finder.roots, vec![expected_finder_path]);
 
         Ok(())
     }
@@ -88,7 +584,22 @@ mod tests {
 
         let expected_finder_path = Path::new(path_parser::DEFAULT_PATH);
 
-        assert_eq!(finder.path, expected_finder_path);
+        assert_eq!(finder.roots, vec![expected_finder_path]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn initialise_finder_with_roots() -> Result<()> {
+        let finder = Finder::with_roots(["src", "tests"])?;
+
+        assert_eq!(finder.roots, vec![Path::new("src"), Path::new("tests")]);
+
+        let finder = Finder::with_roots(Vec::<&str>::new())?;
+
+        assert_eq!(finder.roots, vec![Path::new(path_parser::DEFAULT_PATH)]);
+
+        assert!(Finder::with_roots(["src", "path/to/nowhere"]).is_err());
 
         Ok(())
     }
@@ -0,0 +1,333 @@
+use rayon::{Scope, ThreadPool};
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::fs::{self, FileType, Metadata};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use super::{FileId, Finder, file_id};
+
+/// A directory on the path from a root to the directory being read
+///
+/// Only tracked while following links, to detect symlink loops.
+struct Ancestor {
+    id: FileId,
+    path: PathBuf,
+    parent: Option<Arc<Ancestor>>,
+}
+
+impl Ancestor {
+    /// Find the ancestor with the given identity, if any
+    fn find(&self, id: &FileId) -> Option<&Path> {
+        let mut current = Some(self);
+        while let Some(ancestor) = current {
+            if ancestor.id == *id {
+                return Some(&ancestor.path);
+            }
+            current = ancestor.parent.as_deref();
+        }
+        None
+    }
+}
+
+/// A directory waiting to be read
+struct DirTask {
+    path: PathBuf,
+    depth: usize,
+    root_device: Option<u64>,
+    ancestors: Option<Arc<Ancestor>>,
+}
+
+/// An entry found while reading a directory
+struct Entry<'n> {
+    path: PathBuf,
+    name: &'n OsStr,
+    depth: usize,
+    /// Type of the link target for followed symlinks
+    file_type: FileType,
+    is_symlink: bool,
+    /// Target metadata, when it was already needed
+    metadata: Option<Metadata>,
+}
+
+/// State shared by every thread taking part in a walk
+struct Walk<'w, 'a> {
+    finder: &'w Finder<'a>,
+    query: Option<&'w str>,
+    verbose: bool,
+    dedupe: bool,
+    seen: Mutex<HashSet<FileId>>,
+    sink: &'w (dyn Fn(PathBuf) + Sync),
+}
+
+/// Walk every root of `finder` in parallel, passing each matching file to `sink`
+///
+/// Each directory is read by its own rayon task, so idle threads steal
+/// whole subtrees from busy ones. Runs on the finder's own pool rather than
+/// the global pool used for searching, whose threads may be blocked waiting
+/// for streamed paths.
+pub(super) fn walk(
+    finder: &Finder,
+    pool: &ThreadPool,
+    query: Option<&str>,
+    verbose: bool,
+    sink: &(dyn Fn(PathBuf) + Sync),
+) {
+    let walk = Walk {
+        finder,
+        query,
+        verbose,
+        dedupe: finder.needs_dedupe(),
+        seen: Mutex::new(HashSet::new()),
+        sink,
+    };
+
+    pool.scope(|scope| {
+        for root in finder.distinct_roots() {
+            walk.visit_root(scope, root);
+        }
+    });
+}
+
+impl<'w> Walk<'w, '_> {
+    fn visit_root(&'w self, scope: &Scope<'w>, root: &Path) {
+        // Roots are always followed, even when links otherwise aren't
+        let metadata = match fs::metadata(root) {
+            Ok(metadata) => metadata,
+            Err(err) => {
+                self.report_unreadable(root, &err);
+                return;
+            }
+        };
+
+        if metadata.is_dir() {
+            let ancestors = self.finder.follow_links.then(|| {
+                Arc::new(Ancestor {
+                    id: file_id(root, &metadata),
+                    path: root.to_path_buf(),
+                    parent: None,
+                })
+            });
+            if self.finder.max_depth != Some(0) {
+                let task = DirTask {
+                    path: root.to_path_buf(),
+                    depth: 0,
+                    root_device: device(&metadata),
+                    ancestors,
+                };
+                scope.spawn(move |scope| self.read_dir(scope, task));
+            }
+        } else {
+            self.consider(Entry {
+                path: root.to_path_buf(),
+                name: root.file_name().unwrap_or(root.as_os_str()),
+                depth: 0,
+                file_type: metadata.file_type(),
+                is_symlink: false,
+                metadata: Some(metadata),
+            });
+        }
+    }
+
+    fn read_dir(&'w self, scope: &Scope<'w>, dir: DirTask) {
+        let entries = match fs::read_dir(&dir.path) {
+            Ok(entries) => entries,
+            Err(err) => {
+                self.report_unreadable(&dir.path, &err);
+                return;
+            }
+        };
+
+        for entry in entries {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(err) => {
+                    self.report_unreadable(&dir.path, &err);
+                    continue;
+                }
+            };
+            match entry.file_type() {
+                Ok(file_type) => {
+                    self.visit(scope, entry.path(), &entry.file_name(), file_type, &dir)
+                }
+                Err(err) => self.report_unreadable(&entry.path(), &err),
+            }
+        }
+    }
+
+    fn visit(
+        &'w self,
+        scope: &Scope<'w>,
+        path: PathBuf,
+        name: &OsStr,
+        file_type: FileType,
+        parent: &DirTask,
+    ) {
+        let depth = parent.depth + 1;
+        if !self.finder.is_visible(name, depth) {
+            return;
+        }
+
+        // Resolve followed symlinks to the type of their target
+        let is_symlink = file_type.is_symlink();
+        let (file_type, metadata) = if is_symlink && self.finder.follow_links {
+            match fs::metadata(&path) {
+                Ok(metadata) => (metadata.file_type(), Some(metadata)),
+                Err(err) => {
+                    // Broken links can still be listed as symlinks
+                    self.report_unreadable(&path, &err);
+                    (file_type, None)
+                }
+            }
+        } else {
+            if self.verbose && is_symlink && fs::metadata(&path).is_err() {
+                eprintln!("Warning: Broken symlink: {}", path.display());
+            }
+            (file_type, None)
+        };
+
+        let entry = Entry {
+            path,
+            name,
+            depth,
+            file_type,
+            is_symlink,
+            metadata,
+        };
+        if file_type.is_dir() {
+            self.enter_dir(scope, entry, parent);
+        } else {
+            self.consider(entry);
+        }
+    }
+
+    fn enter_dir(&'w self, scope: &Scope<'w>, mut entry: Entry, parent: &DirTask) {
+        let needs_metadata = self.finder.follow_links || self.finder.same_file_system;
+        if entry.metadata.is_none() && needs_metadata {
+            match fs::metadata(&entry.path) {
+                Ok(metadata) => entry.metadata = Some(metadata),
+                Err(err) => {
+                    self.report_unreadable(&entry.path, &err);
+                    return;
+                }
+            }
+        }
+
+        if self.finder.same_file_system
+            && let Some(metadata) = &entry.metadata
+            && device(metadata) != parent.root_device
+        {
+            if self.verbose {
+                eprintln!("Warning: Skipping mount point: {}", entry.path.display());
+            }
+            return;
+        }
+
+        let ancestors = match (&parent.ancestors, &entry.metadata) {
+            (Some(ancestors), Some(metadata)) => {
+                let id = file_id(&entry.path, metadata);
+                if let Some(ancestor) = ancestors.find(&id) {
+                    if self.verbose {
+                        eprintln!(
+                            "Warning: Symlink loop: {} points to ancestor {}",
+                            entry.path.display(),
+                            ancestor.display()
+                        );
+                    }
+                    return;
+                }
+                Some(Arc::new(Ancestor {
+                    id,
+                    path: entry.path.clone(),
+                    parent: Some(Arc::clone(ancestors)),
+                }))
+            }
+            _ => None,
+        };
+
+        let depth = entry.depth;
+        let path = entry.path.clone();
+        self.consider(entry);
+
+        if self.finder.max_depth.is_some_and(|max| depth >= max) {
+            return;
+        }
+
+        let task = DirTask {
+            path,
+            depth,
+            root_device: parent.root_device,
+            ancestors,
+        };
+        scope.spawn(move |scope| self.read_dir(scope, task));
+    }
+
+    /// Pass an entry to the sink if it passes every filter
+    fn consider(&self, entry: Entry) {
+        if entry.depth < self.finder.min_depth.unwrap_or(0)
+            || !self
+                .finder
+                .matches_kind(&entry.path, entry.file_type, entry.is_symlink)
+            || !self.finder.matches_file_name(entry.name, self.query)
+        {
+            return;
+        }
+
+        // Only stat entries when something needs their metadata
+        if self.dedupe || !self.finder.filters.is_empty() {
+            let metadata = match entry.metadata {
+                Some(metadata) => metadata,
+                None => match self.finder.entry_metadata(&entry.path) {
+                    Ok(metadata) => metadata,
+                    Err(err) => {
+                        if self.verbose {
+                            eprintln!(
+                                "Warning: Cannot read metadata: {} ({})",
+                                entry.path.display(),
+                                err
+                            );
+                        }
+                        return;
+                    }
+                },
+            };
+
+            if !self.finder.matches_metadata(&metadata) {
+                return;
+            }
+            let id = self
+                .finder
+                .entry_id(&entry.path, &metadata, entry.is_symlink);
+            if self.dedupe && !self.seen.lock().unwrap().insert(id) {
+                return;
+            }
+        }
+
+        (self.sink)(entry.path);
+    }
+
+    fn report_unreadable(&self, path: &Path, err: &std::io::Error) {
+        if !self.verbose {
+            return;
+        }
+
+        let is_symlink = path.symlink_metadata().is_ok_and(|m| m.is_symlink());
+        if err.kind() == ErrorKind::NotFound && is_symlink {
+            eprintln!("Warning: Broken symlink: {}", path.display());
+        } else {
+            eprintln!("Warning: Cannot read: {} ({})", path.display(), err);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn device(metadata: &Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.dev())
+}
+
+#[cfg(not(unix))]
+fn device(_metadata: &Metadata) -> Option<u64> {
+    None
+}
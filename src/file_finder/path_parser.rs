@@ -1,6 +1,6 @@
 use anyhow::{Context, Result};
 use std::io::{Error, ErrorKind};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 pub const DEFAULT_PATH: &str = ".";
 
@@ -25,6 +25,58 @@ pub fn parse(path: Option<&str>) -> Result<&Path> {
     }
 }
 
+/// Remove duplicate roots, keeping the first occurrence of each
+///
+/// Roots are compared by canonical path and by file identity, so links and
+/// other mounts of a directory are recognised too. Roots inside another root
+/// are dropped too when `reaches(outer, nested)` says walking the outer root
+/// covers them; `nested` is canonicalized and `outer` is its ancestor.
+pub fn distinct_roots<'a>(
+    roots: &[&'a Path],
+    reaches: impl Fn(&Path, &Path) -> bool,
+) -> Vec<&'a Path> {
+    let canonical: Vec<PathBuf> = roots
+        .iter()
+        .map(|root| root.canonicalize().unwrap_or_else(|_| root.to_path_buf()))
+        .collect();
+
+    roots
+        .iter()
+        .enumerate()
+        .filter(|&(i, _)| {
+            !canonical.iter().enumerate().any(|(j, other)| {
+                if i == j {
+                    false
+                } else if same_directory(&canonical[i], other) {
+                    j < i
+                } else {
+                    canonical[i].ancestors().skip(1).any(|ancestor| {
+                        same_directory(ancestor, other) && reaches(ancestor, &canonical[i])
+                    })
+                }
+            })
+        })
+        .map(|(_, root)| *root)
+        .collect()
+}
+
+/// Whether two paths lead to the same directory
+fn same_directory(a: &Path, b: &Path) -> bool {
+    if a == b {
+        return true;
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        if let (Ok(a), Ok(b)) = (a.metadata(), b.metadata()) {
+            return (a.dev(), a.ino()) == (b.dev(), b.ino());
+        }
+    }
+    false
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -69,4 +121,50 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn distinct_roots_removes_overlap() {
+        let roots = [
+            Path::new("src"),
+            Path::new("./src"),
+            Path::new("src/output"),
+            Path::new("tests"),
+        ];
+
+        assert_eq!(
+            distinct_roots(&roots, |_, _| true),
+            vec![Path::new("src"), Path::new("tests")]
+        );
+        assert_eq!(
+            distinct_roots(&roots, |_, _| false),
+            vec![
+                Path::new("src"),
+                Path::new("src/output"),
+                Path::new("tests")
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn distinct_roots_through_links() -> Result<()> {
+        use std::os::unix::fs::symlink;
+
+        let dir = tempfile::tempdir()?;
+        std::fs::create_dir_all(dir.path().join("src/output"))?;
+        symlink(dir.path().join("src"), dir.path().join("link"))?;
+
+        let src = dir.path().join("src");
+        let link = dir.path().join("link");
+        let nested = dir.path().join("link/output");
+        let roots = [src.as_path(), link.as_path(), nested.as_path()];
+
+        assert_eq!(distinct_roots(&roots, |_, _| true), vec![src.as_path()]);
+        assert_eq!(
+            distinct_roots(&roots, |_, _| false),
+            vec![src.as_path(), nested.as_path()]
+        );
+
+        Ok(())
+    }
 }
@@ -1,20 +1,28 @@
 use anyhow::{Context, Result};
 use rayon::prelude::*;
+use std::collections::BTreeMap;
 use std::fs::File;
-use std::io::{BufRead, BufReader, ErrorKind};
-use std::path::PathBuf;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 
+pub mod exec;
 pub mod file_finder;
 pub mod output;
 pub mod searcher;
+pub mod sort;
 
 // Re-export commonly used types for convenience
 pub use file_finder::Finder;
 pub use output::{
-    ColourMode, CountOutput, FilesOnlyOutput, JsonOutput, Outputs, SearchMatch, StandardOutput,
+    ColourMode, ColourScheme, CountOutput, CsvColumn, CsvOutput, FilesOnlyOutput, HyperlinkFormat,
+    JsonFormat, JsonLinesOutput, JsonOutput, LongOutput, Outputs, RgJsonOutput, SarifOutput,
+    SearchMatch, SearchStats, StandardOutput, Template, TemplateOutput, UserColourSpec,
+    VimgrepOutput,
 };
-pub use searcher::{ReSearcher, SearchResult, Searcher, Searches};
+pub use searcher::{NamedCapture, ReSearcher, SearchResult, Searcher, Searches};
+pub use sort::{SortKey, SortOrder};
 
 /// Buffer size for reading files (8KB)
 ///
@@ -27,50 +35,174 @@ const CHUNK_SIZE: usize = 8192;
 /// Stores match information temporarily before writing to output
 struct BufferedMatch {
     line_number: usize,
+    byte_offset: u64,
     content: String,
     match_positions: Vec<(usize, usize)>,
+    captures: Vec<Vec<searcher::NamedCapture>>,
+    /// Exact bytes of a line that isn't valid UTF-8
+    raw_content: Option<Vec<u8>>,
+    line_ending: &'static str,
 }
 
-pub fn search_files(
+/// Everything learned from searching one file
+struct SearchedFile {
+    matches: Vec<BufferedMatch>,
+    /// Bytes read from the file
+    bytes: u64,
+    /// Why the file couldn't be fully read, if it couldn't
+    error: Option<String>,
+}
+
+/// Running totals shared between the threads of a search
+#[derive(Default)]
+struct SearchTotals {
+    files: AtomicUsize,
+    bytes: AtomicU64,
+}
+
+impl SearchTotals {
+    fn add(&self, searched: &SearchedFile) {
+        self.files.fetch_add(1, Ordering::Relaxed);
+        self.bytes.fetch_add(searched.bytes, Ordering::Relaxed);
+    }
+
+    fn stats(&self) -> output::SearchStats {
+        output::SearchStats {
+            files_searched: self.files.load(Ordering::Relaxed),
+            bytes_searched: self.bytes.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Search every path for matches, writing results to `output`
+///
+/// Paths are consumed as they arrive, so a streaming source such as
+/// `Finder::stream` lets searching start before traversal has finished.
+pub fn search_files<I>(
     searcher: impl searcher::Searches + Sync,
-    paths: impl IntoIterator<Item = PathBuf>,
+    paths: I,
     verbose: bool,
     output: &mut dyn output::Outputs,
-) -> Result<()> {
-    // Collect paths into a vector for parallel processing
-    let paths: Vec<PathBuf> = paths.into_iter().collect();
-
+) -> Result<()>
+where
+    I: IntoIterator<Item = PathBuf>,
+    I::IntoIter: Send,
+{
     // Wrap output in a Mutex so multiple threads can safely write to it
     // Mutex ensures only one thread writes at a time
+    let invalid_utf8 = output.searches_invalid_utf8();
+    let record_errors = output.records_errors();
     let output_mutex = Mutex::new(output);
+    let totals = SearchTotals::default();
 
     // Process files in parallel using rayon
-    // par_iter() splits work across available CPU cores
-    paths.par_iter().try_for_each(|path| {
+    // par_bridge() hands paths to idle worker threads as they are produced
+    paths.into_iter().par_bridge().try_for_each(|path| {
         // Each thread processes one file independently
-        search_file_parallel(&searcher, path, verbose, &output_mutex)
-            .context(format!("searching in '{}'", path.display()))
-            .or_else(|e| {
-                // Check if it's an encoding error (can continue)
-                if let Some(io_err) = e.downcast_ref::<std::io::Error>()
-                    && io_err.kind() == ErrorKind::InvalidData
-                {
-                    if verbose {
-                        eprintln!("Warning: Cannot read file '{}': {}", path.display(), io_err);
-                    }
-                    Ok(())
-                } else {
-                    // Other errors are fatal
-                    Err(e)
-                }
-            })
+        search_file_parallel(
+            &searcher,
+            &path,
+            verbose,
+            invalid_utf8,
+            record_errors,
+            &totals,
+            &output_mutex,
+        )
     })?;
 
     // All threads done, finalize output
-    output_mutex.into_inner().unwrap().finalize();
+    let output = output_mutex.into_inner().unwrap();
+    output.write_stats(&totals.stats());
+    output.finalize();
     Ok(())
 }
 
+/// Search every path for matches, writing results to `output` in a
+/// deterministic order
+///
+/// Files are still searched in parallel. Each file's matches are buffered
+/// and released as soon as every file before it has been written, so only
+/// out-of-order files are held in memory. Sorting by match count needs
+/// every result before anything can be written.
+pub fn search_files_sorted(
+    searcher: impl searcher::Searches + Sync,
+    paths: impl IntoIterator<Item = PathBuf>,
+    order: SortOrder,
+    verbose: bool,
+    output: &mut dyn output::Outputs,
+) -> Result<()> {
+    // The order is only known once every path has been discovered
+    let paths = sort::sort_paths(paths.into_iter().collect(), order);
+    let invalid_utf8 = output.searches_invalid_utf8();
+    let record_errors = output.records_errors();
+    let totals = SearchTotals::default();
+
+    if order.key == SortKey::Matches {
+        let mut results = paths
+            .into_par_iter()
+            .map(|path| {
+                search_file(&searcher, &path, verbose, invalid_utf8, record_errors)
+                    .map(|searched| (path, searched))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // Stable sort keeps files with equal counts in path order
+        results.sort_by(|(_, a), (_, b)| order.direct(a.matches.len().cmp(&b.matches.len())));
+
+        for (path, searched) in results {
+            totals.add(&searched);
+            write_matches(output, &path, searched);
+        }
+        output.write_stats(&totals.stats());
+        output.finalize();
+        return Ok(());
+    }
+
+    let emitter = Mutex::new(OrderedEmitter {
+        paths: &paths,
+        next: 0,
+        pending: BTreeMap::new(),
+        output,
+    });
+
+    // par_bridge() hands out files roughly in order, which keeps the
+    // reorder buffer small and lets output start early
+    paths
+        .iter()
+        .enumerate()
+        .par_bridge()
+        .try_for_each(|(index, path)| {
+            let searched = search_file(&searcher, path, verbose, invalid_utf8, record_errors)?;
+            totals.add(&searched);
+            emitter.lock().unwrap().complete(index, searched);
+            Ok::<(), anyhow::Error>(())
+        })?;
+
+    let output = emitter.into_inner().unwrap().output;
+    output.write_stats(&totals.stats());
+    output.finalize();
+    Ok(())
+}
+
+/// Reorder buffer which writes each file's matches once all earlier files are written
+struct OrderedEmitter<'p, 'o> {
+    paths: &'p [PathBuf],
+    next: usize,
+    pending: BTreeMap<usize, SearchedFile>,
+    output: &'o mut dyn output::Outputs,
+}
+
+impl OrderedEmitter<'_, '_> {
+    fn complete(&mut self, index: usize, searched: SearchedFile) {
+        self.pending.insert(index, searched);
+
+        while let Some(searched) = self.pending.remove(&self.next) {
+            write_matches(self.output, &self.paths[self.next], searched);
+            self.next += 1;
+        }
+    }
+}
+
 /// Search a single file in parallel mode
 /// Takes a Mutex-wrapped output to safely write from multiple threads
 ///
@@ -81,74 +213,183 @@ fn search_file_parallel(
     searcher: &impl searcher::Searches,
     path: &PathBuf,
     verbose: bool,
+    invalid_utf8: bool,
+    record_errors: bool,
+    totals: &SearchTotals,
     output: &Mutex<&mut dyn output::Outputs>,
 ) -> Result<()> {
+    let searched = search_file(searcher, path, verbose, invalid_utf8, record_errors)?;
+    totals.add(&searched);
+
+    // Write all matches for this file in a single critical section
+    // This reduces mutex operations from N (number of matches) to 1
+    if !searched.matches.is_empty() || searched.error.is_some() {
+        let mut output_guard = output.lock().unwrap();
+        write_matches(&mut **output_guard, path, searched);
+        // Lock is automatically released when output_guard goes out of scope
+    }
+
+    Ok(())
+}
+
+/// Search a single file, collecting its matches in line order
+///
+/// Files that can't be opened or read (permissions, a file removed after it
+/// was found, I/O errors) fail the search, unless `record_errors` is set:
+/// then they're skipped with a warning in verbose mode and produce no
+/// matches but an error.
+fn search_file(
+    searcher: &impl searcher::Searches,
+    path: &PathBuf,
+    verbose: bool,
+    invalid_utf8: bool,
+    record_errors: bool,
+) -> Result<SearchedFile> {
+    let result = read_matches(searcher, path, verbose, invalid_utf8);
+    if !record_errors {
+        return result.context(format!("searching in '{}'", path.display()));
+    }
+
+    Ok(result.unwrap_or_else(|e| {
+        let message = match e.downcast_ref::<std::io::Error>() {
+            Some(io_err) => io_err.to_string(),
+            None => format!("{:#}", e),
+        };
+        if verbose {
+            eprintln!(
+                "Warning: Cannot read file '{}': {}",
+                path.display(),
+                message
+            );
+        }
+        SearchedFile {
+            matches: Vec::new(),
+            bytes: 0,
+            error: Some(message),
+        }
+    }))
+}
+
+/// Search a file line by line; lines that aren't valid UTF-8 are searched
+/// lossily decoded with `invalid_utf8`, otherwise skipped and reported
+fn read_matches(
+    searcher: &impl searcher::Searches,
+    path: &PathBuf,
+    verbose: bool,
+    invalid_utf8: bool,
+) -> Result<SearchedFile> {
     // Open file and create buffered reader for efficient streaming
     let file = File::open(path).context(format!("failed to open '{}'", path.display()))?;
+    let mut error = None;
 
-    let reader = BufReader::with_capacity(CHUNK_SIZE, file);
+    let mut reader = BufReader::with_capacity(CHUNK_SIZE, file);
     let mut rownum = 1;
+    // Offset of the current line from the start of the file
+    let mut offset = 0;
+    let mut buffer = Vec::new();
 
     // Batch matches in a thread-local buffer to minimize mutex locking
     // Instead of locking once per match, we lock once per file
     let mut buffered_matches: Vec<BufferedMatch> = Vec::new();
 
     // Stream through file line by line, processing as we go
-    for line in reader.lines() {
-        match line {
+    loop {
+        buffer.clear();
+        let read = reader.read_until(b'\n', &mut buffer).context(format!(
+            "reading line {} in '{}'",
+            rownum,
+            path.display()
+        ))?;
+        if read == 0 {
+            break;
+        }
+
+        let (line, line_ending) = split_line_ending(&buffer);
+        match std::str::from_utf8(line) {
             Ok(content) => {
                 // Search this single line
-                if let Some(result) = searcher.search_line(&content, rownum) {
+                if let Some(result) = searcher.search_line(content, rownum) {
                     // Store match in local buffer (no locking yet!)
                     buffered_matches.push(BufferedMatch {
                         line_number: result.rownum,
-                        content: result.line.clone(),
-                        match_positions: result.match_positions.clone(),
+                        byte_offset: offset,
+                        content: result.line,
+                        match_positions: result.match_positions,
+                        captures: result.captures,
+                        raw_content: None,
+                        line_ending,
+                    });
+                }
+            }
+            Err(_) if invalid_utf8 => {
+                let content = String::from_utf8_lossy(line);
+                if let Some(result) = searcher.search_line(&content, rownum) {
+                    buffered_matches.push(BufferedMatch {
+                        line_number: result.rownum,
+                        byte_offset: offset,
+                        content: result.line,
+                        match_positions: result.match_positions,
+                        captures: result.captures,
+                        raw_content: Some(line.to_vec()),
+                        line_ending,
                     });
                 }
-                rownum += 1;
             }
             Err(e) => {
-                if e.kind() == ErrorKind::InvalidData {
-                    if verbose {
-                        eprintln!(
-                            "Warning: Cannot read line {} in file '{}': {}",
-                            rownum,
-                            path.display(),
-                            e
-                        );
-                    }
-                    // Continue to next line on encoding errors
-                    rownum += 1;
-                    continue;
-                } else {
-                    return Err(e).context(format!(
-                        "reading line {} in '{}'",
+                if verbose {
+                    eprintln!(
+                        "Warning: Cannot read line {} in file '{}': {}",
                         rownum,
-                        path.display()
-                    ));
+                        path.display(),
+                        e
+                    );
                 }
+                // Report the first undecodable line, then continue to the next
+                error.get_or_insert_with(|| format!("line {}: {}", rownum, e));
             }
         }
+        rownum += 1;
+        offset += read as u64;
     }
 
-    // Write all matches for this file in a single critical section
-    // This reduces mutex operations from N (number of matches) to 1
-    if !buffered_matches.is_empty() {
-        let mut output_guard = output.lock().unwrap();
-        for buffered in buffered_matches {
-            let search_match = output::SearchMatch {
-                path: path.as_path(),
-                line_number: buffered.line_number,
-                content: &buffered.content,
-                match_positions: &buffered.match_positions,
-            };
-            output_guard.write_match(&search_match);
-        }
-        // Lock is automatically released when output_guard goes out of scope
-    }
+    Ok(SearchedFile {
+        matches: buffered_matches,
+        bytes: offset,
+        error,
+    })
+}
 
-    Ok(())
+/// A line without its trailing `\n` or `\r\n`, and the terminator removed
+fn split_line_ending(line: &[u8]) -> (&[u8], &'static str) {
+    let stripped = line.strip_suffix(b"\n").unwrap_or(line);
+    let stripped = stripped.strip_suffix(b"\r").unwrap_or(stripped);
+    let ending = match &line[stripped.len()..] {
+        b"\r\n" => "\r\n",
+        b"\n" => "\n",
+        b"\r" => "\r",
+        _ => "",
+    };
+    (stripped, ending)
+}
+
+/// Write a file's error, if any, and buffered matches to the output
+fn write_matches(output: &mut dyn output::Outputs, path: &Path, searched: SearchedFile) {
+    if let Some(error) = &searched.error {
+        output.write_error(path, error);
+    }
+    for buffered in searched.matches {
+        let search_match = output::SearchMatch {
+            path,
+            line_number: buffered.line_number,
+            byte_offset: buffered.byte_offset,
+            content: &buffered.content,
+            match_positions: &buffered.match_positions,
+            captures: &buffered.captures,
+            raw_content: buffered.raw_content.as_deref(),
+            line_ending: buffered.line_ending,
+        };
+        output.write_match(&search_match);
+    }
 }
 
 #[cfg(test)]
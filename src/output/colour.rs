@@ -1,5 +1,8 @@
+use anyhow::{Context, Error, Result, bail};
 use std::env;
-use termcolor::ColorChoice;
+use std::io::{self, IsTerminal};
+use std::str::FromStr;
+use termcolor::{Color, ColorChoice, ColorSpec};
 
 /// Colour mode for output
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -56,19 +59,252 @@ impl ColourMode {
     }
 
     /// Convert to termcolor's ColorChoice
+    ///
+    /// termcolor's own auto mode doesn't check for a TTY, so colour is
+    /// ruled out here when stdout is redirected.
     pub(crate) fn to_color_choice(self) -> ColorChoice {
         match self {
-            ColourMode::Auto => ColorChoice::Auto,
+            ColourMode::Auto if io::stdout().is_terminal() => ColorChoice::Auto,
+            ColourMode::Auto => ColorChoice::Never,
             ColourMode::Always => ColorChoice::Always,
             ColourMode::Never => ColorChoice::Never,
         }
     }
 }
 
+/// Part of the output a colour spec applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColourTarget {
+    Path,
+    Line,
+    Match,
+}
+
+/// Text style a colour spec turns on or off
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColourStyle {
+    Bold,
+    Intense,
+    Underline,
+    Italic,
+    Dimmed,
+}
+
+/// What a colour spec changes
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ColourChange {
+    /// Clear all colours and styles
+    None,
+    Fg(Color),
+    Bg(Color),
+    Style(ColourStyle, bool),
+}
+
+/// One user colour setting, `{target}:{attribute}:{value}` or `{target}:none`
+///
+/// Targets are `path`, `line` and `match`. Attributes are `fg` and `bg`
+/// (a colour name, an ANSI 256 number, `R,G,B` or `#rrggbb`) and `style`
+/// (`bold`, `intense`, `underline`, `italic`, `dimmed`, or any of them
+/// prefixed with `no`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserColourSpec {
+    target: ColourTarget,
+    change: ColourChange,
+}
+
+impl FromStr for UserColourSpec {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let parts: Vec<&str> = s.splitn(3, ':').collect();
+        let target = match parts[0] {
+            "path" => ColourTarget::Path,
+            "line" => ColourTarget::Line,
+            "match" => ColourTarget::Match,
+            other => bail!(
+                "unknown colour target '{}' in '{}' (expected path, line or match)",
+                other,
+                s
+            ),
+        };
+
+        let change = match parts[1..] {
+            ["none"] => ColourChange::None,
+            ["fg", colour] => ColourChange::Fg(parse_colour(colour)?),
+            ["bg", colour] => ColourChange::Bg(parse_colour(colour)?),
+            ["style", style] => {
+                let (name, on) = match style.strip_prefix("no") {
+                    Some(name) => (name, false),
+                    None => (style, true),
+                };
+                let style = match name {
+                    "bold" => ColourStyle::Bold,
+                    "intense" => ColourStyle::Intense,
+                    "underline" => ColourStyle::Underline,
+                    "italic" => ColourStyle::Italic,
+                    "dimmed" => ColourStyle::Dimmed,
+                    _ => bail!(
+                        "unknown style '{}' (expected bold, intense, underline, italic or dimmed, optionally prefixed with no)",
+                        style
+                    ),
+                };
+                ColourChange::Style(style, on)
+            }
+            _ => bail!(
+                "invalid colour spec '{}' (expected {{target}}:fg|bg|style:{{value}} or {{target}}:none)",
+                s
+            ),
+        };
+
+        Ok(UserColourSpec { target, change })
+    }
+}
+
+/// Parse a colour name, ANSI 256 number, `R,G,B` triple or `#rrggbb`
+pub(crate) fn parse_colour(s: &str) -> Result<Color> {
+    if let Some(hex) = s.strip_prefix('#') {
+        let channel = |i: usize| {
+            hex.get(i..i + 2)
+                .and_then(|c| u8::from_str_radix(c, 16).ok())
+        };
+        return match (hex.len(), channel(0), channel(2), channel(4)) {
+            (6, Some(r), Some(g), Some(b)) => Ok(Color::Rgb(r, g, b)),
+            _ => bail!("invalid colour '{}' (expected #rrggbb)", s),
+        };
+    }
+    s.parse::<Color>()
+        .map_err(|e| Error::msg(e.to_string()))
+        .with_context(|| format!("invalid colour '{}'", s))
+}
+
+/// Colours for each part of the output, shared by every coloured output mode
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColourScheme {
+    pub path: ColorSpec,
+    pub line: ColorSpec,
+    pub matched: ColorSpec,
+}
+
+impl Default for ColourScheme {
+    /// Green paths, cyan line numbers and bold white-on-blue matches
+    fn default() -> Self {
+        let mut path = ColorSpec::new();
+        path.set_fg(Some(Color::Green));
+
+        let mut line = ColorSpec::new();
+        line.set_fg(Some(Color::Cyan));
+
+        let mut matched = ColorSpec::new();
+        matched.set_fg(Some(Color::White));
+        matched.set_bg(Some(Color::Blue));
+        matched.set_bold(true);
+
+        ColourScheme {
+            path,
+            line,
+            matched,
+        }
+    }
+}
+
+impl ColourScheme {
+    /// The default scheme with the specs in `FINDERS_COLORS` applied, then
+    /// those given on the command line, so later specs win
+    ///
+    /// `FINDERS_COLORS` holds specs separated by spaces or semicolons.
+    pub fn from_env(specs: &[UserColourSpec]) -> Result<Self> {
+        let mut scheme = ColourScheme::default();
+        if let Ok(list) = env::var("FINDERS_COLORS") {
+            for spec in parse_spec_list(&list).context("parsing FINDERS_COLORS")? {
+                scheme.apply(&spec);
+            }
+        }
+        for spec in specs {
+            scheme.apply(spec);
+        }
+        Ok(scheme)
+    }
+
+    /// Apply one spec on top of the current colours
+    pub fn apply(&mut self, spec: &UserColourSpec) {
+        let colour = match spec.target {
+            ColourTarget::Path => &mut self.path,
+            ColourTarget::Line => &mut self.line,
+            ColourTarget::Match => &mut self.matched,
+        };
+        match spec.change {
+            ColourChange::None => colour.clear(),
+            ColourChange::Fg(fg) => {
+                colour.set_fg(Some(fg));
+            }
+            ColourChange::Bg(bg) => {
+                colour.set_bg(Some(bg));
+            }
+            ColourChange::Style(style, on) => {
+                match style {
+                    ColourStyle::Bold => colour.set_bold(on),
+                    ColourStyle::Intense => colour.set_intense(on),
+                    ColourStyle::Underline => colour.set_underline(on),
+                    ColourStyle::Italic => colour.set_italic(on),
+                    ColourStyle::Dimmed => colour.set_dimmed(on),
+                };
+            }
+        }
+    }
+}
+
+/// Parse specs separated by whitespace or semicolons
+fn parse_spec_list(list: &str) -> Result<Vec<UserColourSpec>> {
+    list.split(|c: char| c == ';' || c.is_whitespace())
+        .filter(|spec| !spec.is_empty())
+        .map(str::parse)
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn parse_scheme(specs: &str) -> Result<ColourScheme> {
+        let mut scheme = ColourScheme::default();
+        for spec in parse_spec_list(specs)? {
+            scheme.apply(&spec);
+        }
+        Ok(scheme)
+    }
+
+    #[test]
+    fn test_colour_specs() -> Result<()> {
+        let scheme =
+            parse_scheme("path:fg:magenta; line:fg:208 match:bg:#1e90ff;match:style:nobold")?;
+        assert_eq!(scheme.path.fg(), Some(&Color::Magenta));
+        assert_eq!(scheme.line.fg(), Some(&Color::Ansi256(208)));
+        assert_eq!(scheme.matched.bg(), Some(&Color::Rgb(0x1e, 0x90, 0xff)));
+        assert_eq!(scheme.matched.fg(), Some(&Color::White));
+        assert!(!scheme.matched.bold());
+
+        let scheme = parse_scheme("path:none path:style:underline line:fg:10,20,30")?;
+        assert_eq!(scheme.path.fg(), None);
+        assert!(scheme.path.underline());
+        assert_eq!(scheme.line.fg(), Some(&Color::Rgb(10, 20, 30)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_colour_specs() {
+        for spec in [
+            "paths:fg:red",
+            "path:fg",
+            "path:fg:mauve",
+            "path:fg:#12345",
+            "path:style:blink",
+            "path:border:red",
+        ] {
+            assert!(spec.parse::<UserColourSpec>().is_err(), "{}", spec);
+        }
+    }
+
     #[test]
     fn test_colour_flag_takes_priority() {
         // CLI flags should override environment variables
@@ -1,50 +1,88 @@
 use std::io::{self, Write};
-use std::path::Path;
-use termcolor::{Color, ColorSpec, StandardStream, WriteColor};
+use std::path::{Path, PathBuf};
+use termcolor::{StandardStream, WriteColor};
 
-use super::{ColourMode, Outputs, SearchMatch};
+use super::hyperlink::{end_hyperlink, start_hyperlink};
+use super::{ColourMode, ColourScheme, HyperlinkFormat, Outputs, SearchMatch, write_path};
 
 /// Count output mode (like grep -c)
 /// Outputs match count per file in format: path:count
 pub struct CountOutput {
     stdout: StandardStream,
-    path_colour: ColorSpec,
-    current_file: Option<String>,
+    colours: ColourScheme,
+    current_file: Option<PathBuf>,
     current_count: usize,
+    null_separated: bool,
+    hyperlink_format: Option<HyperlinkFormat>,
 }
 
 impl CountOutput {
     pub fn new(colour_mode: ColourMode) -> Self {
         let stdout = StandardStream::stdout(colour_mode.to_color_choice());
 
-        let mut path_colour = ColorSpec::new();
-        path_colour.set_fg(Some(Color::Green));
-
         CountOutput {
             stdout,
-            path_colour,
+            colours: ColourScheme::default(),
             current_file: None,
             current_count: 0,
+            null_separated: false,
+            hyperlink_format: None,
         }
     }
 
-    fn write_coloured_path(&mut self, path: &Path) -> io::Result<()> {
-        self.stdout.set_color(&self.path_colour)?;
-        write!(self.stdout, "{}", path.to_string_lossy())?;
+    /// Use these colours instead of the defaults (`--colors`)
+    pub fn colours(mut self, colours: ColourScheme) -> Self {
+        self.colours = colours;
+        self
+    }
+
+    /// Link each path to its file with an OSC 8 hyperlink, when colour is
+    /// enabled (`--hyperlink-format`)
+    pub fn hyperlinks(mut self, format: Option<HyperlinkFormat>) -> Self {
+        self.hyperlink_format = format;
+        self
+    }
+
+    /// Separate each path from its count with a NUL byte instead of `:` (`-0`)
+    pub fn null_separated(mut self, null_separated: bool) -> Self {
+        self.null_separated = null_separated;
+        self
+    }
+
+    fn write_count_line(&mut self, path: &Path, count: usize) -> io::Result<()> {
+        self.write_coloured_path(path, None)?;
+        if self.null_separated {
+            self.stdout.write_all(b"\0")?;
+        } else {
+            write!(self.stdout, ":")?;
+        }
+        writeln!(self.stdout, "{}", count)?;
+        self.stdout.flush()
+    }
+
+    fn write_coloured_path(&mut self, path: &Path, line: Option<usize>) -> io::Result<()> {
+        let linked = start_hyperlink(
+            &mut self.stdout,
+            self.hyperlink_format.as_ref(),
+            path,
+            line,
+            None,
+        )?;
+        self.stdout.set_color(&self.colours.path)?;
+        write_path(&mut self.stdout, path)?;
         self.stdout.reset()?;
+        if linked {
+            end_hyperlink(&mut self.stdout)?;
+        }
         Ok(())
     }
 
     fn flush_current_file(&mut self) {
-        if let Some(ref path) = self.current_file
+        if let Some(path) = self.current_file.take()
             && self.current_count > 0
         {
             // Output path:count
-            let path_buf = std::path::PathBuf::from(path);
-            let _ = self.write_coloured_path(&path_buf);
-            let _ = write!(self.stdout, ":{}", self.current_count);
-            let _ = writeln!(self.stdout);
-            let _ = self.stdout.flush();
+            let _ = self.write_count_line(&path, self.current_count);
         }
     }
 }
@@ -57,12 +95,10 @@ impl Default for CountOutput {
 
 impl Outputs for CountOutput {
     fn write_match(&mut self, match_result: &SearchMatch) {
-        let path_str = match_result.path.to_string_lossy().to_string();
-
         // If we're starting a new file, flush the previous file's count
-        if self.current_file.as_ref() != Some(&path_str) {
+        if self.current_file.as_deref() != Some(match_result.path) {
             self.flush_current_file();
-            self.current_file = Some(path_str);
+            self.current_file = Some(match_result.path.to_path_buf());
             self.current_count = 1;
         } else {
             self.current_count += 1;
@@ -71,10 +107,7 @@ impl Outputs for CountOutput {
 
     fn write_file(&mut self, path: &Path) {
         // For file-only mode, just output path with count 0
-        let _ = self.write_coloured_path(path);
-        let _ = write!(self.stdout, ":0");
-        let _ = writeln!(self.stdout);
-        let _ = self.stdout.flush();
+        let _ = self.write_count_line(path, 0);
     }
 
     fn finalize(&mut self) {
@@ -86,7 +119,6 @@ impl Outputs for CountOutput {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::path::PathBuf;
 
     #[test]
     fn test_count_aggregation() {
@@ -97,26 +129,38 @@ mod tests {
         output.write_match(&SearchMatch {
             path: &path,
             line_number: 1,
+            byte_offset: 0,
             content: "first match",
             match_positions: &[(0, 5)],
+            captures: &[],
+            raw_content: None,
+            line_ending: "\n",
         });
 
         output.write_match(&SearchMatch {
             path: &path,
             line_number: 3,
+            byte_offset: 0,
             content: "second match",
             match_positions: &[(0, 6)],
+            captures: &[],
+            raw_content: None,
+            line_ending: "\n",
         });
 
         output.write_match(&SearchMatch {
             path: &path,
             line_number: 7,
+            byte_offset: 0,
             content: "third match",
             match_positions: &[(0, 5)],
+            captures: &[],
+            raw_content: None,
+            line_ending: "\n",
         });
 
         // Verify count is accumulated
-        assert_eq!(output.current_file, Some("test.txt".to_string()));
+        assert_eq!(output.current_file, Some(PathBuf::from("test.txt")));
         assert_eq!(output.current_count, 3);
     }
 
@@ -130,27 +174,39 @@ mod tests {
         output.write_match(&SearchMatch {
             path: &path1,
             line_number: 1,
+            byte_offset: 0,
             content: "match",
             match_positions: &[(0, 5)],
+            captures: &[],
+            raw_content: None,
+            line_ending: "\n",
         });
 
         output.write_match(&SearchMatch {
             path: &path1,
             line_number: 2,
+            byte_offset: 0,
             content: "match",
             match_positions: &[(0, 5)],
+            captures: &[],
+            raw_content: None,
+            line_ending: "\n",
         });
 
         // Switch to second file
         output.write_match(&SearchMatch {
             path: &path2,
             line_number: 1,
+            byte_offset: 0,
             content: "match",
             match_positions: &[(0, 5)],
+            captures: &[],
+            raw_content: None,
+            line_ending: "\n",
         });
 
         // Second file should be current with count 1
-        assert_eq!(output.current_file, Some("file2.txt".to_string()));
+        assert_eq!(output.current_file, Some(PathBuf::from("file2.txt")));
         assert_eq!(output.current_count, 1);
     }
 }
@@ -0,0 +1,299 @@
+use anyhow::{Error, Result, bail};
+use std::fs::{self, Metadata};
+use std::io::{self, BufWriter, Stdout, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use super::long::{format_permissions, format_timestamp};
+use super::{Outputs, SearchMatch, write_path};
+
+/// A column of CSV/TSV output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsvColumn {
+    Path,
+    /// Line number, 1-based
+    Line,
+    /// Column of the match in characters, 1-based
+    Column,
+    /// The matched text
+    Match,
+    /// The whole matching line
+    Content,
+    /// File size in bytes
+    Size,
+    /// Modification time as an RFC 3339 UTC timestamp
+    Modified,
+    /// Permission string in ls format
+    Permissions,
+}
+
+impl FromStr for CsvColumn {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "path" => Ok(CsvColumn::Path),
+            "line" => Ok(CsvColumn::Line),
+            "column" => Ok(CsvColumn::Column),
+            "match" => Ok(CsvColumn::Match),
+            "content" => Ok(CsvColumn::Content),
+            "size" => Ok(CsvColumn::Size),
+            "modified" | "mtime" => Ok(CsvColumn::Modified),
+            "permissions" | "perm" => Ok(CsvColumn::Permissions),
+            _ => bail!(
+                "unknown column '{}' (expected path, line, column, match, content, size, modified or permissions)",
+                s
+            ),
+        }
+    }
+}
+
+impl CsvColumn {
+    /// Default columns when searching
+    pub const SEARCH: &[CsvColumn] = &[
+        CsvColumn::Path,
+        CsvColumn::Line,
+        CsvColumn::Column,
+        CsvColumn::Match,
+        CsvColumn::Content,
+    ];
+
+    /// Default columns when listing files
+    pub const LISTING: &[CsvColumn] = &[
+        CsvColumn::Path,
+        CsvColumn::Size,
+        CsvColumn::Modified,
+        CsvColumn::Permissions,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CsvColumn::Path => "path",
+            CsvColumn::Line => "line",
+            CsvColumn::Column => "column",
+            CsvColumn::Match => "match",
+            CsvColumn::Content => "content",
+            CsvColumn::Size => "size",
+            CsvColumn::Modified => "modified",
+            CsvColumn::Permissions => "permissions",
+        }
+    }
+
+    /// Whether the column describes a match, so only applies when searching
+    pub fn is_match_column(self) -> bool {
+        matches!(
+            self,
+            CsvColumn::Line | CsvColumn::Column | CsvColumn::Match | CsvColumn::Content
+        )
+    }
+}
+
+/// CSV/TSV output mode
+/// Writes a header row, then one row per match (or per matching line if
+/// neither `column` nor `match` is selected), or one row per listed file
+pub struct CsvOutput {
+    stdout: BufWriter<Stdout>,
+    columns: Vec<CsvColumn>,
+    delimiter: u8,
+    header_written: bool,
+    current_file: Option<PathBuf>,
+    /// Metadata of the current file, read once per file when a column needs it
+    metadata: Option<Metadata>,
+}
+
+impl CsvOutput {
+    pub fn new(columns: Vec<CsvColumn>) -> Self {
+        CsvOutput {
+            stdout: BufWriter::new(io::stdout()),
+            columns,
+            delimiter: b',',
+            header_written: false,
+            current_file: None,
+            metadata: None,
+        }
+    }
+
+    /// Separate fields with tabs instead of commas (`--tsv`)
+    pub fn tab_separated(mut self, tab_separated: bool) -> Self {
+        self.delimiter = if tab_separated { b'\t' } else { b',' };
+        self
+    }
+
+    fn write_header(&mut self) -> io::Result<()> {
+        if !self.header_written {
+            self.header_written = true;
+            let names: Vec<&[u8]> = self
+                .columns
+                .iter()
+                .map(|column| column.name().as_bytes())
+                .collect();
+            self.write_row(&names)?;
+        }
+        Ok(())
+    }
+
+    fn write_row(&mut self, fields: &[&[u8]]) -> io::Result<()> {
+        for (index, field) in fields.iter().enumerate() {
+            if index > 0 {
+                self.stdout.write_all(&[self.delimiter])?;
+            }
+            write_field(&mut self.stdout, field, self.delimiter)?;
+        }
+        self.stdout.write_all(b"\n")
+    }
+
+    /// Start a new file, flushing the rows of the previous one
+    fn enter_file(&mut self, path: &Path) {
+        if self.current_file.as_deref() == Some(path) {
+            return;
+        }
+        let _ = self.stdout.flush();
+        self.current_file = Some(path.to_path_buf());
+        self.metadata = self
+            .columns
+            .iter()
+            .any(|column| {
+                matches!(
+                    column,
+                    CsvColumn::Size | CsvColumn::Modified | CsvColumn::Permissions
+                )
+            })
+            .then(|| fs::symlink_metadata(path).ok())
+            .flatten();
+    }
+
+    /// Bytes of a column describing the current file, or None for match columns
+    ///
+    /// Paths are written exactly as the OS represents them, as in the other
+    /// text outputs.
+    fn file_field(&self, column: CsvColumn, path: &Path) -> Option<Vec<u8>> {
+        let metadata = self.metadata.as_ref();
+        let text = match column {
+            CsvColumn::Path => {
+                let mut bytes = Vec::new();
+                let _ = write_path(&mut bytes, path);
+                return Some(bytes);
+            }
+            CsvColumn::Size => metadata.map_or(String::new(), |m| m.len().to_string()),
+            CsvColumn::Modified => metadata
+                .and_then(|m| m.modified().ok())
+                .map_or(String::new(), format_timestamp),
+            CsvColumn::Permissions => metadata.map_or(String::new(), format_permissions),
+            _ => return None,
+        };
+        Some(text.into_bytes())
+    }
+}
+
+impl Outputs for CsvOutput {
+    fn write_match(&mut self, match_result: &SearchMatch) {
+        let _ = self.write_header();
+        self.enter_file(match_result.path);
+
+        let line = match_result.content;
+        let line_number = match_result.line_number.to_string();
+        let per_match = self
+            .columns
+            .iter()
+            .any(|column| matches!(column, CsvColumn::Column | CsvColumn::Match));
+        let spans: &[(usize, usize)] = if per_match {
+            match_result.match_positions
+        } else {
+            // One row for the whole line
+            &match_result.match_positions[..match_result.match_positions.len().min(1)]
+        };
+
+        for &(start, end) in spans {
+            let Some((matched, chars_before)) = match_result.span_text((start, end)) else {
+                continue;
+            };
+            let column = (chars_before + 1).to_string();
+            let fields: Vec<Vec<u8>> = self
+                .columns
+                .iter()
+                .map(|&field| match field {
+                    CsvColumn::Line => line_number.clone().into_bytes(),
+                    CsvColumn::Column => column.clone().into_bytes(),
+                    CsvColumn::Match => matched.as_bytes().to_vec(),
+                    CsvColumn::Content => line.as_bytes().to_vec(),
+                    _ => self
+                        .file_field(field, match_result.path)
+                        .unwrap_or_default(),
+                })
+                .collect();
+            let fields: Vec<&[u8]> = fields.iter().map(Vec::as_slice).collect();
+            let _ = self.write_row(&fields);
+        }
+    }
+
+    fn write_file(&mut self, path: &Path) {
+        let _ = self.write_header();
+        self.enter_file(path);
+
+        let fields: Vec<Vec<u8>> = self
+            .columns
+            .iter()
+            .map(|&column| self.file_field(column, path).unwrap_or_default())
+            .collect();
+        let fields: Vec<&[u8]> = fields.iter().map(Vec::as_slice).collect();
+        let _ = self.write_row(&fields);
+    }
+
+    fn finalize(&mut self) {
+        // Write the header even when nothing was found
+        let _ = self.write_header();
+        let _ = self.stdout.flush();
+    }
+}
+
+/// Write a field, quoting it if it contains the delimiter, a quote or a
+/// line break (RFC 4180; quotes inside are doubled)
+fn write_field(out: &mut impl Write, field: &[u8], delimiter: u8) -> io::Result<()> {
+    let needs_quotes = field
+        .iter()
+        .any(|&byte| byte == delimiter || matches!(byte, b'"' | b'\n' | b'\r'));
+
+    if !needs_quotes {
+        return out.write_all(field);
+    }
+    out.write_all(b"\"")?;
+    for (index, part) in field.split(|&byte| byte == b'"').enumerate() {
+        if index > 0 {
+            out.write_all(b"\"\"")?;
+        }
+        out.write_all(part)?;
+    }
+    out.write_all(b"\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(text: &str, delimiter: u8) -> String {
+        let mut out = Vec::new();
+        write_field(&mut out, text.as_bytes(), delimiter).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_field_quoting() {
+        assert_eq!(field("plain text", b','), "plain text");
+        assert_eq!(field("a, b", b','), "\"a, b\"");
+        assert_eq!(field("say \"hi\"", b','), "\"say \"\"hi\"\"\"");
+        assert_eq!(field("two\nlines", b','), "\"two\nlines\"");
+        assert_eq!(field("a, b", b'\t'), "a, b");
+        assert_eq!(field("a\tb", b'\t'), "\"a\tb\"");
+    }
+
+    #[test]
+    fn test_parse_columns() -> Result<()> {
+        assert_eq!("path".parse::<CsvColumn>()?, CsvColumn::Path);
+        assert_eq!("mtime".parse::<CsvColumn>()?, CsvColumn::Modified);
+        assert!("colour".parse::<CsvColumn>().is_err());
+        assert!(CsvColumn::Content.is_match_column());
+        assert!(!CsvColumn::Size.is_match_column());
+
+        Ok(())
+    }
+}
@@ -1,35 +1,73 @@
 use std::io::{self, Write};
-use std::path::Path;
-use termcolor::{Color, ColorSpec, StandardStream, WriteColor};
+use std::path::{Path, PathBuf};
+use termcolor::{StandardStream, WriteColor};
 
-use super::{ColourMode, Outputs, SearchMatch};
+use super::hyperlink::{end_hyperlink, start_hyperlink};
+use super::{ColourMode, ColourScheme, HyperlinkFormat, Outputs, SearchMatch, write_path};
 
 /// Files-only output mode (like grep -l)
 /// Outputs only file paths that contain matches, one per line
 pub struct FilesOnlyOutput {
     stdout: StandardStream,
-    path_colour: ColorSpec,
-    current_file: Option<String>,
+    colours: ColourScheme,
+    current_file: Option<PathBuf>,
+    null_separated: bool,
+    hyperlink_format: Option<HyperlinkFormat>,
 }
 
 impl FilesOnlyOutput {
     pub fn new(colour_mode: ColourMode) -> Self {
         let stdout = StandardStream::stdout(colour_mode.to_color_choice());
 
-        let mut path_colour = ColorSpec::new();
-        path_colour.set_fg(Some(Color::Green));
-
         FilesOnlyOutput {
             stdout,
-            path_colour,
+            colours: ColourScheme::default(),
             current_file: None,
+            null_separated: false,
+            hyperlink_format: None,
         }
     }
 
-    fn write_coloured_path(&mut self, path: &Path) -> io::Result<()> {
-        self.stdout.set_color(&self.path_colour)?;
-        write!(self.stdout, "{}", path.to_string_lossy())?;
+    /// Use these colours instead of the defaults (`--colors`)
+    pub fn colours(mut self, colours: ColourScheme) -> Self {
+        self.colours = colours;
+        self
+    }
+
+    /// Link each path to its first matching line with an OSC 8 hyperlink,
+    /// when colour is enabled (`--hyperlink-format`)
+    pub fn hyperlinks(mut self, format: Option<HyperlinkFormat>) -> Self {
+        self.hyperlink_format = format;
+        self
+    }
+
+    /// Terminate each path with a NUL byte instead of a newline (`-0`)
+    pub fn null_separated(mut self, null_separated: bool) -> Self {
+        self.null_separated = null_separated;
+        self
+    }
+
+    fn write_path_line(&mut self, path: &Path, line: Option<usize>) -> io::Result<()> {
+        self.write_coloured_path(path, line)?;
+        let terminator: &[u8] = if self.null_separated { b"\0" } else { b"\n" };
+        self.stdout.write_all(terminator)?;
+        self.stdout.flush()
+    }
+
+    fn write_coloured_path(&mut self, path: &Path, line: Option<usize>) -> io::Result<()> {
+        let linked = start_hyperlink(
+            &mut self.stdout,
+            self.hyperlink_format.as_ref(),
+            path,
+            line,
+            None,
+        )?;
+        self.stdout.set_color(&self.colours.path)?;
+        write_path(&mut self.stdout, path)?;
         self.stdout.reset()?;
+        if linked {
+            end_hyperlink(&mut self.stdout)?;
+        }
         Ok(())
     }
 }
@@ -43,27 +81,21 @@ impl Default for FilesOnlyOutput {
 impl Outputs for FilesOnlyOutput {
     fn write_match(&mut self, match_result: &SearchMatch) {
         // Only output each file path once (first match)
-        let path_str = match_result.path.to_string_lossy().to_string();
-        if self.current_file.as_ref() != Some(&path_str) {
-            let _ = self.write_coloured_path(match_result.path);
-            let _ = writeln!(self.stdout);
-            let _ = self.stdout.flush();
-            self.current_file = Some(path_str);
+        if self.current_file.as_deref() != Some(match_result.path) {
+            let _ = self.write_path_line(match_result.path, Some(match_result.line_number));
+            self.current_file = Some(match_result.path.to_path_buf());
         }
     }
 
     fn write_file(&mut self, path: &Path) {
         // Same as StandardOutput for file-only mode
-        let _ = self.write_coloured_path(path);
-        let _ = writeln!(self.stdout);
-        let _ = self.stdout.flush();
+        let _ = self.write_path_line(path, None);
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::path::PathBuf;
 
     #[test]
     fn test_files_only_deduplication() {
@@ -74,26 +106,38 @@ mod tests {
         output.write_match(&SearchMatch {
             path: &path,
             line_number: 1,
+            byte_offset: 0,
             content: "first match",
             match_positions: &[(0, 5)],
+            captures: &[],
+            raw_content: None,
+            line_ending: "\n",
         });
 
         output.write_match(&SearchMatch {
             path: &path,
             line_number: 3,
+            byte_offset: 0,
             content: "second match",
             match_positions: &[(0, 6)],
+            captures: &[],
+            raw_content: None,
+            line_ending: "\n",
         });
 
         output.write_match(&SearchMatch {
             path: &path,
             line_number: 7,
+            byte_offset: 0,
             content: "third match",
             match_positions: &[(0, 5)],
+            captures: &[],
+            raw_content: None,
+            line_ending: "\n",
         });
 
         // Verify file is tracked (only output once)
-        assert_eq!(output.current_file, Some("test.txt".to_string()));
+        assert_eq!(output.current_file, Some(PathBuf::from("test.txt")));
     }
 
     #[test]
@@ -106,18 +150,26 @@ mod tests {
         output.write_match(&SearchMatch {
             path: &path1,
             line_number: 1,
+            byte_offset: 0,
             content: "match",
             match_positions: &[(0, 5)],
+            captures: &[],
+            raw_content: None,
+            line_ending: "\n",
         });
 
         output.write_match(&SearchMatch {
             path: &path2,
             line_number: 1,
+            byte_offset: 0,
             content: "match",
             match_positions: &[(0, 5)],
+            captures: &[],
+            raw_content: None,
+            line_ending: "\n",
         });
 
         // Should track the second file
-        assert_eq!(output.current_file, Some("file2.txt".to_string()));
+        assert_eq!(output.current_file, Some(PathBuf::from("file2.txt")));
     }
 }
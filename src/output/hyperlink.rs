@@ -0,0 +1,167 @@
+use anyhow::{Error, Result, bail};
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::path::{self, Path};
+use std::str::FromStr;
+use termcolor::WriteColor;
+
+/// Template for the URLs of OSC 8 terminal hyperlinks (`--hyperlink-format`)
+///
+/// A preset name (`file`, `vscode`, `idea`) or a custom template using
+/// `{path}`, `{line}` and `{column}`. `{path}` is the absolute,
+/// percent-encoded path, always starting with `/`; `{line}` and `{column}`
+/// are 1-based and are 1 when there's no match to point at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HyperlinkFormat {
+    template: String,
+}
+
+impl FromStr for HyperlinkFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let template = match s {
+            "file" => "file://{path}",
+            "vscode" => "vscode://file{path}:{line}:{column}",
+            "idea" => "idea://open?file={path}&line={line}&column={column}",
+            _ => s,
+        };
+
+        if !template.contains("{path}") {
+            bail!(
+                "hyperlink format '{}' must be file, vscode, idea or a template containing {{path}}",
+                s
+            );
+        }
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            let Some(end) = rest[start..].find('}') else {
+                bail!("unclosed '{{' in hyperlink format '{}'", s);
+            };
+            let placeholder = &rest[start..start + end + 1];
+            if !matches!(placeholder, "{path}" | "{line}" | "{column}") {
+                bail!(
+                    "unknown placeholder {} in hyperlink format (expected {{path}}, {{line}} or {{column}})",
+                    placeholder
+                );
+            }
+            rest = &rest[start + end + 1..];
+        }
+
+        Ok(HyperlinkFormat {
+            template: template.to_string(),
+        })
+    }
+}
+
+impl HyperlinkFormat {
+    /// URL for a path, pointing at a line and column if given, or None if
+    /// the path can't be made absolute
+    pub fn url(&self, path: &Path, line: Option<usize>, column: Option<usize>) -> Option<String> {
+        let path = path::absolute(path).ok()?;
+        let mut encoded = String::new();
+        if !path.as_os_str().as_encoded_bytes().starts_with(b"/") {
+            // Windows drive paths, e.g. file:///C:/src
+            encoded.push('/');
+        }
+        percent_encode_path(&mut encoded, &path);
+
+        // The encoded path has no braces, so it can't introduce placeholders
+        Some(
+            self.template
+                .replace("{path}", &encoded)
+                .replace("{line}", &line.unwrap_or(1).to_string())
+                .replace("{column}", &column.unwrap_or(1).to_string()),
+        )
+    }
+}
+
+/// Open a hyperlink for a path if there's a format and the stream allows
+/// escape codes, returning whether one was opened (close it with
+/// [`end_hyperlink`])
+pub(crate) fn start_hyperlink(
+    out: &mut impl WriteColor,
+    format: Option<&HyperlinkFormat>,
+    path: &Path,
+    line: Option<usize>,
+    column: Option<usize>,
+) -> io::Result<bool> {
+    match format.and_then(|format| format.url(path, line, column)) {
+        Some(url) if out.supports_color() => {
+            write!(out, "\x1b]8;;{}\x1b\\", url)?;
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
+
+/// Close a hyperlink opened with [`start_hyperlink`]
+pub(crate) fn end_hyperlink(out: &mut impl Write) -> io::Result<()> {
+    out.write_all(b"\x1b]8;;\x1b\\")
+}
+
+/// Append a path to a URI, percent-encoding every byte outside the
+/// unreserved set byte by byte (`/` is kept, and on Windows `\` becomes `/`)
+pub(crate) fn percent_encode_path(uri: &mut String, path: &Path) {
+    for &byte in path.as_os_str().as_encoded_bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                uri.push(byte as char)
+            }
+            b'\\' if cfg!(windows) => uri.push('/'),
+            b':' if cfg!(windows) => uri.push(':'),
+            _ => {
+                let _ = write!(uri, "%{:02X}", byte);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hyperlink_format() {
+        assert!("file".parse::<HyperlinkFormat>().is_ok());
+        assert!(
+            "myeditor://{path}#L{line}"
+                .parse::<HyperlinkFormat>()
+                .is_ok()
+        );
+        assert!("myeditor://open".parse::<HyperlinkFormat>().is_err());
+        assert!("x://{path}:{row}".parse::<HyperlinkFormat>().is_err());
+        assert!("x://{path}:{line".parse::<HyperlinkFormat>().is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hyperlink_urls() -> Result<()> {
+        let path = Path::new("/src/a b.rs");
+        let url = |format: &str, line, column| -> Result<String> {
+            Ok(format
+                .parse::<HyperlinkFormat>()?
+                .url(path, line, column)
+                .unwrap())
+        };
+
+        assert_eq!(url("file", Some(3), None)?, "file:///src/a%20b.rs");
+        assert_eq!(
+            url("vscode", Some(3), Some(7))?,
+            "vscode://file/src/a%20b.rs:3:7"
+        );
+        assert_eq!(
+            url("idea", None, None)?,
+            "idea://open?file=/src/a%20b.rs&line=1&column=1"
+        );
+
+        let relative = "file"
+            .parse::<HyperlinkFormat>()?
+            .url(Path::new("lib.rs"), None, None)
+            .unwrap();
+        assert!(relative.starts_with("file:///"));
+        assert!(relative.ends_with("/lib.rs"));
+
+        Ok(())
+    }
+}
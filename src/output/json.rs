@@ -1,35 +1,263 @@
+use anyhow::{Error, Result, bail};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::Path;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Instant;
 
-use super::{Outputs, SearchMatch};
+use super::long::{format_permissions, format_timestamp};
+use super::{Outputs, SearchMatch, SearchStats, span_text};
 
-/// JSON match representation
+/// Version of the `--json` document layout, bumped on incompatible changes
+///
+/// Version 1 was a bare array of files; version 2 wraps it in a
+/// [`JsonReport`] and adds spans, counts, a summary and errors.
+pub const JSON_SCHEMA_VERSION: u32 = 2;
+
+/// Layout of `--json` output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonFormat {
+    /// A single [`JsonReport`] document (the default)
+    Finders,
+    /// ripgrep's `--json` messages, one per line (see `RgJsonOutput`)
+    Rg,
+}
+
+impl FromStr for JsonFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "finders" => Ok(JsonFormat::Finders),
+            "rg" | "ripgrep" => Ok(JsonFormat::Rg),
+            _ => bail!("unknown JSON format '{}' (expected finders or rg)", s),
+        }
+    }
+}
+
+/// The whole `--json` document
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonReport {
+    /// Always [`JSON_SCHEMA_VERSION`]
+    pub version: u32,
+    pub files: Vec<JsonFile>,
+    pub summary: JsonSummary,
+    pub errors: Vec<JsonError>,
+}
+
+/// Totals for the whole run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct JsonSummary {
+    /// Files in `files`
+    pub files: usize,
+    /// Files whose contents were searched, including those without matches
+    pub files_searched: usize,
+    /// Bytes read from the searched files
+    pub bytes_searched: u64,
+    pub matched_lines: usize,
+    pub matches: usize,
+    pub elapsed_ms: u128,
+}
+
+/// A file that couldn't be fully read
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonError {
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path_base64: Option<String>,
+    pub message: String,
+}
+
+/// JSON match representation (one per matching line)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JsonMatch {
     pub line: usize,
     pub content: String,
+    /// Each match within the line
+    #[serde(default)]
+    pub submatches: Vec<JsonSubmatch>,
+}
+
+/// Position of a match (or named capture group) within its line
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JsonSubmatch {
+    pub text: String,
+    /// Byte offsets, end exclusive
+    pub start: usize,
+    pub end: usize,
+    /// Offsets in Unicode scalar values (chars), end exclusive
+    pub char_start: usize,
+    pub char_end: usize,
+    /// Named capture groups, null for groups that didn't participate
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub captures: BTreeMap<String, Option<JsonSubmatch>>,
+}
+
+impl JsonSubmatch {
+    /// Describe the byte range `start..end` of `line`, or None if it isn't a
+    /// range of whole characters within the line
+    pub fn new(line: &str, (start, end): (usize, usize)) -> Option<Self> {
+        let (text, char_start) = span_text(line, (start, end))?;
+
+        Some(JsonSubmatch {
+            text: text.to_string(),
+            start,
+            end,
+            char_start,
+            char_end: char_start + text.chars().count(),
+            captures: BTreeMap::new(),
+        })
+    }
+}
+
+impl JsonMatch {
+    /// Build a match from a search result, including its submatches and captures
+    pub fn new(match_result: &SearchMatch) -> Self {
+        let line = match_result.content;
+        let submatches = match_result
+            .match_positions
+            .iter()
+            .enumerate()
+            .filter_map(|(index, &span)| {
+                let mut submatch = JsonSubmatch::new(line, span)?;
+                for capture in match_result.captures.get(index).into_iter().flatten() {
+                    submatch.captures.insert(
+                        capture.name.clone(),
+                        capture.span.and_then(|span| JsonSubmatch::new(line, span)),
+                    );
+                }
+                Some(submatch)
+            })
+            .collect();
+
+        JsonMatch {
+            line: match_result.line_number,
+            content: line.to_string(),
+            submatches,
+        }
+    }
 }
 
 /// JSON file representation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JsonFile {
+    /// Path as text (lossy if the path isn't valid UTF-8)
     pub path: String,
+    /// Exact path bytes, base64 encoded; only present when `path` is lossy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path_base64: Option<String>,
+    /// Number of lines with matches
+    #[serde(default)]
+    pub matched_lines: usize,
+    /// Number of matches, counting each one on a line
+    #[serde(default)]
+    pub match_count: usize,
     pub matches: Vec<JsonMatch>,
+    /// File metadata, only present in long listing mode
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<JsonMetadata>,
+}
+
+/// JSON representation of a file's metadata (for `--long --json`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonMetadata {
+    /// Permission string in ls format, e.g. `-rw-r--r--`
+    pub permissions: String,
+    /// Size in bytes
+    pub size: u64,
+    /// Modification time as an RFC 3339 UTC timestamp
+    pub modified: Option<String>,
+}
+
+impl JsonMetadata {
+    /// Read the metadata of a path (of a link itself, not its target)
+    pub fn read(path: &Path) -> Option<Self> {
+        let metadata = fs::symlink_metadata(path).ok()?;
+
+        Some(JsonMetadata {
+            permissions: format_permissions(&metadata),
+            size: metadata.len(),
+            modified: metadata.modified().ok().map(format_timestamp),
+        })
+    }
+}
+
+impl JsonFile {
+    /// Build a file entry, adding `path_base64` for paths that aren't valid UTF-8
+    pub fn new(path: &Path, matches: Vec<JsonMatch>) -> Self {
+        let (path, path_base64) = encode_path(path);
+
+        JsonFile {
+            path,
+            path_base64,
+            matched_lines: matches.len(),
+            match_count: matches.iter().map(|m| m.submatches.len()).sum(),
+            matches,
+            metadata: None,
+        }
+    }
+}
+
+/// Encode a path as text, plus base64 of its exact bytes if it isn't valid UTF-8
+pub(crate) fn encode_path(path: &Path) -> (String, Option<String>) {
+    match path.to_str() {
+        Some(path) => (path.to_string(), None),
+        None => (
+            path.to_string_lossy().to_string(),
+            Some(BASE64.encode(path.as_os_str().as_encoded_bytes())),
+        ),
+    }
 }
 
 /// JSON output mode
 /// Outputs structured JSON for machine processing
 pub struct JsonOutput {
-    files: HashMap<String, Vec<JsonMatch>>,
+    files: HashMap<PathBuf, Vec<JsonMatch>>,
+    /// Paths in the order they first arrived
+    order: Vec<PathBuf>,
+    errors: Vec<(PathBuf, String)>,
+    stats: SearchStats,
+    metadata: bool,
+    sorted: bool,
+    started: Instant,
 }
 
 impl JsonOutput {
     pub fn new() -> Self {
         JsonOutput {
             files: HashMap::new(),
+            order: Vec::new(),
+            errors: Vec::new(),
+            stats: SearchStats::default(),
+            metadata: false,
+            sorted: false,
+            started: Instant::now(),
         }
     }
+
+    /// Results arrive in a requested order (`--sort`/`--sortr`), so keep it
+    /// rather than sorting files by path
+    pub fn sorted(mut self, sorted: bool) -> Self {
+        self.sorted = sorted;
+        self
+    }
+
+    /// The matches of a path, remembering when it first arrived
+    fn file_matches(&mut self, path: &Path) -> &mut Vec<JsonMatch> {
+        if !self.files.contains_key(path) {
+            self.order.push(path.to_path_buf());
+        }
+        self.files.entry(path.to_path_buf()).or_default()
+    }
+
+    /// Include each file's permissions, size and modification time (`--long --json`)
+    pub fn metadata(mut self, metadata: bool) -> Self {
+        self.metadata = metadata;
+        self
+    }
 }
 
 impl Default for JsonOutput {
@@ -40,35 +268,72 @@ impl Default for JsonOutput {
 
 impl Outputs for JsonOutput {
     fn write_match(&mut self, match_result: &SearchMatch) {
-        let path_str = match_result.path.to_string_lossy().to_string();
-
-        let json_match = JsonMatch {
-            line: match_result.line_number,
-            content: match_result.content.to_string(),
-        };
-
-        self.files.entry(path_str).or_default().push(json_match);
+        let json_match = JsonMatch::new(match_result);
+        self.file_matches(match_result.path).push(json_match);
     }
 
     fn write_file(&mut self, path: &Path) {
         // For file-only mode, add path with no matches
-        let path_str = path.to_string_lossy().to_string();
-        self.files.entry(path_str).or_default();
+        self.file_matches(path);
+    }
+
+    fn write_error(&mut self, path: &Path, message: &str) {
+        self.errors.push((path.to_path_buf(), message.to_string()));
+    }
+
+    fn records_errors(&self) -> bool {
+        true
+    }
+
+    fn write_stats(&mut self, stats: &SearchStats) {
+        self.stats = *stats;
     }
 
     fn finalize(&mut self) {
-        // Convert HashMap to Vec of JsonFile and serialise
-        let mut output: Vec<JsonFile> = self
-            .files
+        // Sort by path for consistent output, unless already in a requested order
+        if !self.sorted {
+            self.order.sort();
+            self.errors.sort();
+        }
+
+        // Convert to Vec of JsonFile and serialise
+        let files: Vec<JsonFile> = self
+            .order
             .iter()
-            .map(|(path, matches)| JsonFile {
-                path: path.clone(),
-                matches: matches.clone(),
+            .map(|path| JsonFile {
+                metadata: self.metadata.then(|| JsonMetadata::read(path)).flatten(),
+                ..JsonFile::new(path, self.files[path].clone())
             })
             .collect();
 
-        // Sort by path for consistent output
-        output.sort_by(|a, b| a.path.cmp(&b.path));
+        let errors = self
+            .errors
+            .iter()
+            .map(|(path, message)| {
+                let (path, path_base64) = encode_path(path);
+                JsonError {
+                    path,
+                    path_base64,
+                    message: message.clone(),
+                }
+            })
+            .collect();
+
+        let summary = JsonSummary {
+            files: files.len(),
+            files_searched: self.stats.files_searched,
+            bytes_searched: self.stats.bytes_searched,
+            matched_lines: files.iter().map(|file| file.matched_lines).sum(),
+            matches: files.iter().map(|file| file.match_count).sum(),
+            elapsed_ms: self.started.elapsed().as_millis(),
+        };
+
+        let output = JsonReport {
+            version: JSON_SCHEMA_VERSION,
+            files,
+            summary,
+            errors,
+        };
 
         // Serialise and print
         match serde_json::to_string_pretty(&output) {
@@ -81,13 +346,13 @@ impl Outputs for JsonOutput {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::path::PathBuf;
 
     #[test]
     fn test_json_match_serialisation() {
         let json_match = JsonMatch {
             line: 42,
             content: "test content".to_string(),
+            submatches: Vec::new(),
         };
 
         let json = serde_json::to_string(&json_match).unwrap();
@@ -99,14 +364,20 @@ mod tests {
     fn test_json_file_serialisation() {
         let json_file = JsonFile {
             path: "src/test.rs".to_string(),
+            path_base64: None,
+            metadata: None,
+            matched_lines: 2,
+            match_count: 2,
             matches: vec![
                 JsonMatch {
                     line: 1,
                     content: "first line".to_string(),
+                    submatches: Vec::new(),
                 },
                 JsonMatch {
                     line: 5,
                     content: "fifth line".to_string(),
+                    submatches: Vec::new(),
                 },
             ],
         };
@@ -115,6 +386,20 @@ mod tests {
         assert!(json.contains("\"path\":\"src/test.rs\""));
         assert!(json.contains("\"line\":1"));
         assert!(json.contains("\"line\":5"));
+        assert!(!json.contains("path_base64"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_json_file_non_utf8_path() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = Path::new(OsStr::from_bytes(b"caf\xe9.txt"));
+        let json_file = JsonFile::new(path, Vec::new());
+
+        assert_eq!(json_file.path, "caf\u{fffd}.txt");
+        assert_eq!(json_file.path_base64.as_deref(), Some("Y2Fm6S50eHQ="));
     }
 
     #[test]
@@ -126,20 +411,66 @@ mod tests {
         output.write_match(&SearchMatch {
             path: &path,
             line_number: 1,
+            byte_offset: 0,
             content: "first match",
             match_positions: &[(0, 5)],
+            captures: &[],
+            raw_content: None,
+            line_ending: "\n",
         });
 
         output.write_match(&SearchMatch {
             path: &path,
             line_number: 3,
+            byte_offset: 0,
             content: "second match",
             match_positions: &[(0, 6)],
+            captures: &[],
+            raw_content: None,
+            line_ending: "\n",
         });
 
         // Verify matches are stored
         assert_eq!(output.files.len(), 1);
-        assert_eq!(output.files.get("test.txt").unwrap().len(), 2);
+        assert_eq!(output.files.get(Path::new("test.txt")).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_json_match_spans_and_captures() {
+        use crate::searcher::NamedCapture;
+
+        let captures = [vec![NamedCapture {
+            name: "word".to_string(),
+            span: Some((8, 12)),
+        }]];
+        let json_match = JsonMatch::new(&SearchMatch {
+            path: Path::new("test.txt"),
+            line_number: 2,
+            byte_offset: 0,
+            content: "héllo, wörld",
+            match_positions: &[(8, 14)],
+            captures: &captures,
+            raw_content: None,
+            line_ending: "\n",
+        });
+
+        let submatch = &json_match.submatches[0];
+        assert_eq!(submatch.text, "wörld");
+        assert_eq!((submatch.start, submatch.end), (8, 14));
+        assert_eq!((submatch.char_start, submatch.char_end), (7, 12));
+        let word = submatch.captures["word"].as_ref().unwrap();
+        assert_eq!(word.text, "wör");
+        assert_eq!((word.char_start, word.char_end), (7, 10));
+    }
+
+    #[test]
+    fn test_json_metadata_serialisation() {
+        let metadata = JsonMetadata::read(Path::new("Cargo.toml")).unwrap();
+        let json = serde_json::to_string(&metadata).unwrap();
+
+        assert!(json.contains("\"permissions\":\"-"));
+        assert!(json.contains(&format!("\"size\":{}", metadata.size)));
+        assert!(metadata.modified.unwrap().ends_with('Z'));
     }
 
     #[test]
@@ -152,6 +483,6 @@ mod tests {
 
         // Verify file is stored with empty matches
         assert_eq!(output.files.len(), 1);
-        assert_eq!(output.files.get("empty.txt").unwrap().len(), 0);
+        assert_eq!(output.files.get(Path::new("empty.txt")).unwrap().len(), 0);
     }
 }
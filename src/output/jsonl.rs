@@ -0,0 +1,211 @@
+use serde::Serialize;
+use std::io::{self, BufWriter, Stdout, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use super::json::encode_path;
+use super::{Outputs, SearchMatch};
+
+/// Path of the file an event refers to
+#[derive(Debug, Clone, Serialize)]
+pub struct JsonPath {
+    pub path: String,
+    /// Exact path bytes, base64 encoded; only present when `path` is lossy
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path_base64: Option<String>,
+}
+
+/// Byte range of a match within a line
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct JsonSpan {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// One line of JSON Lines output
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JsonEvent<'a> {
+    /// The first match in a file is about to follow
+    Begin {
+        #[serde(flatten)]
+        path: JsonPath,
+    },
+    /// A matching line
+    Match {
+        #[serde(flatten)]
+        path: JsonPath,
+        line: usize,
+        content: &'a str,
+        submatches: Vec<JsonSpan>,
+    },
+    /// Every match in a file has been written
+    End {
+        #[serde(flatten)]
+        path: JsonPath,
+        matches: usize,
+    },
+    /// A file found in file-listing mode
+    File {
+        #[serde(flatten)]
+        path: JsonPath,
+    },
+    /// Totals, written last
+    Summary {
+        files: usize,
+        matches: usize,
+        elapsed_ms: u128,
+    },
+}
+
+impl JsonPath {
+    pub fn new(path: &Path) -> Self {
+        let (path, path_base64) = encode_path(path);
+        JsonPath { path, path_base64 }
+    }
+}
+
+/// JSON Lines output mode
+/// Writes one JSON object per event as soon as it happens, so memory use
+/// doesn't grow with the number of matches
+pub struct JsonLinesOutput {
+    stdout: BufWriter<Stdout>,
+    current_file: Option<PathBuf>,
+    current_count: usize,
+    files: usize,
+    matches: usize,
+    started: Instant,
+}
+
+impl JsonLinesOutput {
+    pub fn new() -> Self {
+        JsonLinesOutput {
+            stdout: BufWriter::new(io::stdout()),
+            current_file: None,
+            current_count: 0,
+            files: 0,
+            matches: 0,
+            started: Instant::now(),
+        }
+    }
+
+    fn write_event(&mut self, event: &JsonEvent) {
+        match serde_json::to_writer(&mut self.stdout, event) {
+            Ok(()) => {
+                let _ = writeln!(self.stdout);
+            }
+            Err(e) => eprintln!("Error serialising JSON: {}", e),
+        }
+    }
+
+    /// Write the end event for the current file, if there is one
+    fn end_current_file(&mut self) {
+        if let Some(path) = self.current_file.take() {
+            self.write_event(&JsonEvent::End {
+                path: JsonPath::new(&path),
+                matches: self.current_count,
+            });
+            // Flush once per file, so results appear as each file finishes
+            let _ = self.stdout.flush();
+        }
+    }
+}
+
+impl Default for JsonLinesOutput {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Outputs for JsonLinesOutput {
+    fn write_match(&mut self, match_result: &SearchMatch) {
+        // Matches for a file arrive together, so a new path starts a new file
+        if self.current_file.as_deref() != Some(match_result.path) {
+            self.end_current_file();
+            self.write_event(&JsonEvent::Begin {
+                path: JsonPath::new(match_result.path),
+            });
+            self.current_file = Some(match_result.path.to_path_buf());
+            self.current_count = 0;
+            self.files += 1;
+        }
+
+        self.write_event(&JsonEvent::Match {
+            path: JsonPath::new(match_result.path),
+            line: match_result.line_number,
+            content: match_result.content,
+            submatches: match_result
+                .match_positions
+                .iter()
+                .filter(|&&span| match_result.span_text(span).is_some())
+                .map(|&(start, end)| JsonSpan { start, end })
+                .collect(),
+        });
+        self.current_count += 1;
+        self.matches += 1;
+    }
+
+    fn write_file(&mut self, path: &Path) {
+        self.write_event(&JsonEvent::File {
+            path: JsonPath::new(path),
+        });
+        let _ = self.stdout.flush();
+        self.files += 1;
+    }
+
+    fn finalize(&mut self) {
+        self.end_current_file();
+        self.write_event(&JsonEvent::Summary {
+            files: self.files,
+            matches: self.matches,
+            elapsed_ms: self.started.elapsed().as_millis(),
+        });
+        let _ = self.stdout.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_event_serialisation() {
+        let event = JsonEvent::Match {
+            path: JsonPath::new(Path::new("src/lib.rs")),
+            line: 3,
+            content: "fn main() {}",
+            submatches: vec![JsonSpan { start: 3, end: 7 }],
+        };
+
+        let json = serde_json::to_string(&event).unwrap();
+        assert_eq!(
+            json,
+            r#"{"type":"match","path":"src/lib.rs","line":3,"content":"fn main() {}","submatches":[{"start":3,"end":7}]}"#
+        );
+    }
+
+    #[test]
+    fn test_json_lines_tracks_files() {
+        let mut output = JsonLinesOutput::new();
+        let path1 = PathBuf::from("file1.txt");
+        let path2 = PathBuf::from("file2.txt");
+
+        for (path, line_number) in [(&path1, 1), (&path1, 4), (&path2, 2)] {
+            output.write_match(&SearchMatch {
+                path,
+                line_number,
+                byte_offset: 0,
+                content: "match",
+                match_positions: &[(0, 5)],
+                captures: &[],
+                raw_content: None,
+                line_ending: "\n",
+            });
+        }
+
+        assert_eq!(output.current_file, Some(path2));
+        assert_eq!(output.current_count, 1);
+        assert_eq!(output.files, 2);
+        assert_eq!(output.matches, 3);
+    }
+}
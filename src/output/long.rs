@@ -0,0 +1,240 @@
+use std::fs::{self, Metadata};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use termcolor::{StandardStream, WriteColor};
+
+use super::{ColourMode, ColourScheme, Outputs, SearchMatch, write_path};
+use crate::file_finder::SECONDS_PER_DAY;
+
+/// Long listing mode (like ls -l)
+/// Outputs permissions, human-readable size, modification time (UTC) and path
+pub struct LongOutput {
+    stdout: StandardStream,
+    colours: ColourScheme,
+    current_file: Option<PathBuf>,
+}
+
+impl LongOutput {
+    pub fn new(colour_mode: ColourMode) -> Self {
+        let stdout = StandardStream::stdout(colour_mode.to_color_choice());
+
+        LongOutput {
+            stdout,
+            colours: ColourScheme::default(),
+            current_file: None,
+        }
+    }
+
+    /// Use these colours instead of the defaults (`--colors`)
+    pub fn colours(mut self, colours: ColourScheme) -> Self {
+        self.colours = colours;
+        self
+    }
+
+    fn write_entry(&mut self, path: &Path) -> io::Result<()> {
+        // Describe links themselves, as ls -l does
+        match fs::symlink_metadata(path) {
+            Ok(metadata) => write!(
+                self.stdout,
+                "{} {:>5} {} ",
+                format_permissions(&metadata),
+                format_size(metadata.len()),
+                metadata
+                    .modified()
+                    .map_or_else(|_| "????-??-?? ??:??".to_string(), format_time)
+            )?,
+            Err(_) => write!(self.stdout, "?????????? {:>5} {:16} ", "?", "?")?,
+        }
+
+        self.stdout.set_color(&self.colours.path)?;
+        write_path(&mut self.stdout, path)?;
+        self.stdout.reset()?;
+
+        if let Ok(target) = fs::read_link(path) {
+            write!(self.stdout, " -> ")?;
+            write_path(&mut self.stdout, &target)?;
+        }
+        writeln!(self.stdout)?;
+        self.stdout.flush()
+    }
+}
+
+impl Default for LongOutput {
+    fn default() -> Self {
+        Self::new(ColourMode::Auto)
+    }
+}
+
+impl Outputs for LongOutput {
+    fn write_match(&mut self, match_result: &SearchMatch) {
+        // List each file with matches once
+        if self.current_file.as_deref() != Some(match_result.path) {
+            let _ = self.write_entry(match_result.path);
+            self.current_file = Some(match_result.path.to_path_buf());
+        }
+    }
+
+    fn write_file(&mut self, path: &Path) {
+        let _ = self.write_entry(path);
+    }
+}
+
+/// Permission string in ls format, e.g. `drwxr-xr-x`
+#[cfg(unix)]
+pub(crate) fn format_permissions(metadata: &Metadata) -> String {
+    use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+    let file_type = metadata.file_type();
+    let kind = if file_type.is_dir() {
+        'd'
+    } else if file_type.is_symlink() {
+        'l'
+    } else if file_type.is_fifo() {
+        'p'
+    } else if file_type.is_socket() {
+        's'
+    } else if file_type.is_char_device() {
+        'c'
+    } else if file_type.is_block_device() {
+        'b'
+    } else {
+        '-'
+    };
+
+    let mode = metadata.permissions().mode();
+    let mut permissions = String::with_capacity(10);
+    permissions.push(kind);
+    // (read bit, write bit, execute bit, special bit, special char) for user, group, other
+    for (shift, special, special_char) in [(6, 0o4000, 's'), (3, 0o2000, 's'), (0, 0o1000, 't')] {
+        let bits = (mode >> shift) & 0o7;
+        permissions.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+        permissions.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+        permissions.push(match (bits & 0o1 != 0, mode & special != 0) {
+            (true, true) => special_char,
+            (false, true) => special_char.to_ascii_uppercase(),
+            (true, false) => 'x',
+            (false, false) => '-',
+        });
+    }
+    permissions
+}
+
+/// Permission string in ls format, approximated from the read-only flag
+#[cfg(not(unix))]
+pub(crate) fn format_permissions(metadata: &Metadata) -> String {
+    let kind = if metadata.is_dir() { 'd' } else { '-' };
+    let access = if metadata.permissions().readonly() {
+        "r--r--r--"
+    } else {
+        "rw-rw-rw-"
+    };
+    format!("{}{}", kind, access)
+}
+
+/// Size with a binary unit suffix, e.g. `512B`, `4.0K`, `12M`
+pub(crate) fn format_size(bytes: u64) -> String {
+    const UNITS: [char; 5] = ['K', 'M', 'G', 'T', 'P'];
+
+    if bytes < 1024 {
+        return format!("{}B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if size < 10.0 {
+        format!("{:.1}{}", size, UNITS[unit])
+    } else {
+        format!("{:.0}{}", size, UNITS[unit])
+    }
+}
+
+/// Time as `YYYY-MM-DD HH:MM` in UTC
+pub(crate) fn format_time(time: SystemTime) -> String {
+    let (date, hours, minutes, _) = split_time(time);
+    format!("{} {:02}:{:02}", date, hours, minutes)
+}
+
+/// Time as an RFC 3339 timestamp in UTC, e.g. `2026-05-01T12:30:00Z`
+pub(crate) fn format_timestamp(time: SystemTime) -> String {
+    let (date, hours, minutes, seconds) = split_time(time);
+    format!("{}T{:02}:{:02}:{:02}Z", date, hours, minutes, seconds)
+}
+
+/// Split a time into its UTC date and time of day
+fn split_time(time: SystemTime) -> (String, u64, u64, u64) {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let (year, month, day) = civil_from_days((seconds / SECONDS_PER_DAY) as i64);
+    let second_of_day = seconds % SECONDS_PER_DAY;
+
+    (
+        format!("{:04}-{:02}-{:02}", year, month, day),
+        second_of_day / 3_600,
+        second_of_day % 3_600 / 60,
+        second_of_day % 60,
+    )
+}
+
+/// Proleptic Gregorian date for a number of days since 1970-01-01
+/// (Howard Hinnant's civil_from_days algorithm)
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(0), "0B");
+        assert_eq!(format_size(1023), "1023B");
+        assert_eq!(format_size(4096), "4.0K");
+        assert_eq!(format_size(1536 * 1024), "1.5M");
+        assert_eq!(format_size(12 * 1024 * 1024), "12M");
+    }
+
+    #[test]
+    fn test_format_time() {
+        let time = UNIX_EPOCH + Duration::from_secs(1_767_225_601);
+        assert_eq!(format_time(time), "2026-01-01 00:00");
+        assert_eq!(format_timestamp(time), "2026-01-01T00:00:01Z");
+        assert_eq!(format_timestamp(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        assert_eq!(
+            format_time(UNIX_EPOCH + Duration::from_secs(951_782_400 + 45_000)),
+            "2000-02-29 12:30"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_format_permissions() {
+        let metadata = fs::metadata("src").unwrap();
+        let permissions = format_permissions(&metadata);
+
+        assert_eq!(permissions.len(), 10);
+        assert!(permissions.starts_with("drwx"));
+    }
+}
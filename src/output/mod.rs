@@ -1,23 +1,83 @@
+use std::io::{self, Write};
 use std::path::Path;
 
+use crate::searcher::NamedCapture;
+
 mod colour;
 mod count;
+mod csv;
 mod files_only;
+mod hyperlink;
 mod json;
+mod jsonl;
+mod long;
+mod rg_json;
+mod sarif;
 mod standard;
+mod template;
+mod vimgrep;
 
-pub use colour::ColourMode;
+pub use colour::{ColourMode, ColourScheme, UserColourSpec};
 pub use count::CountOutput;
+pub use csv::{CsvColumn, CsvOutput};
 pub use files_only::FilesOnlyOutput;
-pub use json::{JsonFile, JsonMatch, JsonOutput};
+pub use hyperlink::HyperlinkFormat;
+pub use json::{
+    JSON_SCHEMA_VERSION, JsonError, JsonFile, JsonFormat, JsonMatch, JsonMetadata, JsonOutput,
+    JsonReport, JsonSubmatch, JsonSummary,
+};
+pub use jsonl::{JsonEvent, JsonLinesOutput, JsonPath, JsonSpan};
+pub use long::LongOutput;
+pub use rg_json::{RgData, RgDuration, RgJsonOutput, RgMessage, RgStats, RgSubmatch};
+pub use sarif::SarifOutput;
 pub use standard::StandardOutput;
+pub use template::{Template, TemplateOutput};
+pub use vimgrep::VimgrepOutput;
 
 /// Result of a search match containing line number and content
 pub struct SearchMatch<'a> {
     pub path: &'a Path,
     pub line_number: usize,
+    /// Offset of the start of the line from the start of the file
+    pub byte_offset: u64,
     pub content: &'a str,
     pub match_positions: &'a [(usize, usize)], // (start, end) byte positions
+    /// Named capture groups of each match (empty unless the regex has named groups)
+    pub captures: &'a [Vec<NamedCapture>],
+    /// Exact bytes of a line that isn't valid UTF-8; `content` then holds it
+    /// lossily decoded (only for outputs that opt in with
+    /// [`Outputs::searches_invalid_utf8`])
+    pub raw_content: Option<&'a [u8]>,
+    /// The terminator removed from the line, exactly as it was in the file:
+    /// usually `"\n"` or `"\r\n"`, empty for a last line without one
+    pub line_ending: &'a str,
+}
+
+impl<'a> SearchMatch<'a> {
+    /// The text of a span of `content` and the number of characters before
+    /// it, or None if the span isn't a range of whole characters within the
+    /// line
+    ///
+    /// Outputs check every match and capture span through this and skip the
+    /// ones it rejects.
+    pub fn span_text(&self, span: (usize, usize)) -> Option<(&'a str, usize)> {
+        span_text(self.content, span)
+    }
+}
+
+/// [`SearchMatch::span_text`] for a line outside a `SearchMatch`
+pub(crate) fn span_text(line: &str, (start, end): (usize, usize)) -> Option<(&str, usize)> {
+    let text = line.get(start..end)?;
+    Some((text, line[..start].chars().count()))
+}
+
+/// Totals for a whole search, reported to the output before it's finalised
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    /// Files whose contents were searched, with or without matches
+    pub files_searched: usize,
+    /// Bytes read from the searched files
+    pub bytes_searched: u64,
 }
 
 /// Trait for different output formats
@@ -29,6 +89,41 @@ pub trait Outputs: Send {
     /// Output a file path (for file-only mode)
     fn write_file(&mut self, path: &Path);
 
+    /// Record a file that couldn't be fully read (most outputs ignore these;
+    /// they're reported on stderr in verbose mode)
+    fn write_error(&mut self, _path: &Path, _message: &str) {}
+
+    /// Whether files that can't be opened or read are recorded through
+    /// write_error and skipped; otherwise they fail the search
+    fn records_errors(&self) -> bool {
+        false
+    }
+
+    /// Whether lines that aren't valid UTF-8 are searched too, lossily decoded
+    /// with their exact bytes in `raw_content`; otherwise they're skipped and
+    /// reported through write_error
+    fn searches_invalid_utf8(&self) -> bool {
+        false
+    }
+
+    /// Record totals for the whole search, called just before finalize
+    fn write_stats(&mut self, _stats: &SearchStats) {}
+
     /// Finalise output (e.g., close JSON array)
     fn finalize(&mut self) {}
 }
+
+/// Write a path exactly as the OS represents it
+///
+/// On Unix this writes the raw bytes, so names that aren't valid UTF-8
+/// are printed unchanged rather than with replacement characters.
+#[cfg(unix)]
+pub(crate) fn write_path(out: &mut impl Write, path: &Path) -> io::Result<()> {
+    use std::os::unix::ffi::OsStrExt;
+    out.write_all(path.as_os_str().as_bytes())
+}
+
+#[cfg(not(unix))]
+pub(crate) fn write_path(out: &mut impl Write, path: &Path) -> io::Result<()> {
+    write!(out, "{}", path.display())
+}
@@ -0,0 +1,399 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde::Serialize;
+use std::fs;
+use std::io::{self, BufWriter, Stdout, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use super::{Outputs, SearchMatch, SearchStats};
+
+/// Data that may not be valid UTF-8: `{"text": ...}` when it is,
+/// otherwise `{"bytes": ...}` holding base64 of the exact bytes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum RgData {
+    Text { text: String },
+    Bytes { bytes: String },
+}
+
+impl RgData {
+    /// Text if the bytes are valid UTF-8, otherwise their base64
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        match std::str::from_utf8(bytes) {
+            Ok(text) => RgData::text(text),
+            Err(_) => RgData::Bytes {
+                bytes: BASE64.encode(bytes),
+            },
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Self {
+        match path.to_str() {
+            Some(text) => RgData::text(text),
+            None => RgData::Bytes {
+                bytes: BASE64.encode(path.as_os_str().as_encoded_bytes()),
+            },
+        }
+    }
+
+    pub fn text(text: &str) -> Self {
+        RgData::Text {
+            text: text.to_string(),
+        }
+    }
+}
+
+/// A match within a line
+#[derive(Debug, Clone, Serialize)]
+pub struct RgSubmatch {
+    #[serde(rename = "match")]
+    pub matched: RgData,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A duration in ripgrep's format
+#[derive(Debug, Clone, Serialize)]
+pub struct RgDuration {
+    pub secs: u64,
+    pub nanos: u32,
+    pub human: String,
+}
+
+impl From<Duration> for RgDuration {
+    fn from(duration: Duration) -> Self {
+        RgDuration {
+            secs: duration.as_secs(),
+            nanos: duration.subsec_nanos(),
+            human: format!("{:0.6}s", duration.as_secs_f64()),
+        }
+    }
+}
+
+/// Statistics for one file (in `end`) or the whole search (in `summary`)
+#[derive(Debug, Clone, Serialize)]
+pub struct RgStats {
+    pub elapsed: RgDuration,
+    pub searches: usize,
+    pub searches_with_match: usize,
+    pub bytes_searched: u64,
+    pub bytes_printed: u64,
+    pub matched_lines: usize,
+    pub matches: usize,
+}
+
+/// One message of ripgrep's `--json` output
+///
+/// finders has no context lines, so ripgrep's `context` message is never
+/// produced.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum RgMessage {
+    Begin {
+        path: RgData,
+    },
+    Match {
+        path: RgData,
+        lines: RgData,
+        line_number: usize,
+        absolute_offset: u64,
+        submatches: Vec<RgSubmatch>,
+    },
+    End {
+        path: RgData,
+        binary_offset: Option<u64>,
+        stats: RgStats,
+    },
+    Summary {
+        elapsed_total: RgDuration,
+        stats: RgStats,
+    },
+}
+
+/// Counters for the file currently being written
+struct FileProgress {
+    path: PathBuf,
+    started: Instant,
+    bytes_printed: u64,
+    matched_lines: usize,
+    matches: usize,
+}
+
+/// ripgrep-compatible JSON output mode (`--json-format rg`)
+/// Writes the same begin/match/end/summary messages as `rg --json`, one
+/// per line, so tools that parse ripgrep's output can read finders'
+pub struct RgJsonOutput {
+    stdout: BufWriter<Stdout>,
+    current: Option<FileProgress>,
+    stats: SearchStats,
+    files_with_matches: usize,
+    elapsed: Duration,
+    bytes_printed: u64,
+    matched_lines: usize,
+    matches: usize,
+    started: Instant,
+}
+
+impl RgJsonOutput {
+    pub fn new() -> Self {
+        RgJsonOutput {
+            stdout: BufWriter::new(io::stdout()),
+            current: None,
+            stats: SearchStats::default(),
+            files_with_matches: 0,
+            elapsed: Duration::ZERO,
+            bytes_printed: 0,
+            matched_lines: 0,
+            matches: 0,
+            started: Instant::now(),
+        }
+    }
+
+    /// Write a message, returning the number of bytes written
+    fn write_message(&mut self, message: &RgMessage) -> u64 {
+        match serde_json::to_vec(message) {
+            Ok(mut json) => {
+                json.push(b'\n');
+                let _ = self.stdout.write_all(&json);
+                json.len() as u64
+            }
+            Err(e) => {
+                eprintln!("Error serialising JSON: {}", e);
+                0
+            }
+        }
+    }
+
+    fn begin_file(&mut self, path: &Path) {
+        let bytes_printed = self.write_message(&RgMessage::Begin {
+            path: RgData::from_path(path),
+        });
+        self.current = Some(FileProgress {
+            path: path.to_path_buf(),
+            started: Instant::now(),
+            bytes_printed,
+            matched_lines: 0,
+            matches: 0,
+        });
+    }
+
+    /// Write the end message for the current file, if there is one
+    fn end_current_file(&mut self) {
+        let Some(file) = self.current.take() else {
+            return;
+        };
+
+        let elapsed = file.started.elapsed();
+        let searched = file.matched_lines > 0;
+        self.write_message(&RgMessage::End {
+            path: RgData::from_path(&file.path),
+            binary_offset: None,
+            stats: RgStats {
+                elapsed: elapsed.into(),
+                searches: usize::from(searched),
+                searches_with_match: usize::from(searched),
+                bytes_searched: if searched {
+                    fs::metadata(&file.path).map_or(0, |metadata| metadata.len())
+                } else {
+                    0
+                },
+                bytes_printed: file.bytes_printed,
+                matched_lines: file.matched_lines,
+                matches: file.matches,
+            },
+        });
+        // Flush once per file, so results appear as each file finishes
+        let _ = self.stdout.flush();
+
+        self.files_with_matches += usize::from(searched);
+        self.elapsed += elapsed;
+        self.bytes_printed += file.bytes_printed;
+        self.matched_lines += file.matched_lines;
+        self.matches += file.matches;
+    }
+}
+
+impl Default for RgJsonOutput {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Outputs for RgJsonOutput {
+    fn write_match(&mut self, match_result: &SearchMatch) {
+        // Matches for a file arrive together, so a new path starts a new file
+        if self.current.as_ref().map(|file| file.path.as_path()) != Some(match_result.path) {
+            self.end_current_file();
+            self.begin_file(match_result.path);
+        }
+
+        let line = match_result.content;
+        let submatches = match match_result.raw_content {
+            // Offsets and matched bytes refer to the line as it is in the file
+            Some(raw) => match_result
+                .match_positions
+                .iter()
+                .filter_map(|&(start, end)| {
+                    match_result.span_text((start, end))?;
+                    let (start, end) = (raw_offset(raw, start), raw_offset(raw, end));
+                    Some(RgSubmatch {
+                        matched: RgData::from_bytes(&raw[start..end]),
+                        start,
+                        end,
+                    })
+                })
+                .collect(),
+            None => match_result
+                .match_positions
+                .iter()
+                .filter_map(|&(start, end)| {
+                    let (matched, _) = match_result.span_text((start, end))?;
+                    Some(RgSubmatch {
+                        matched: RgData::text(matched),
+                        start,
+                        end,
+                    })
+                })
+                .collect(),
+        };
+        // Lines are reported exactly as read, terminator included, as
+        // ripgrep does
+        let ending = match_result.line_ending;
+        let lines = match match_result.raw_content {
+            Some(raw) => RgData::from_bytes(&[raw, ending.as_bytes()].concat()),
+            None => RgData::text(&format!("{}{}", line, ending)),
+        };
+
+        let bytes_printed = self.write_message(&RgMessage::Match {
+            path: RgData::from_path(match_result.path),
+            lines,
+            line_number: match_result.line_number,
+            absolute_offset: match_result.byte_offset,
+            submatches,
+        });
+
+        if let Some(file) = &mut self.current {
+            file.bytes_printed += bytes_printed;
+            file.matched_lines += 1;
+            file.matches += match_result.match_positions.len();
+        }
+    }
+
+    fn write_file(&mut self, path: &Path) {
+        // File-listing mode: an empty begin/end pair per file
+        self.end_current_file();
+        self.begin_file(path);
+        self.end_current_file();
+    }
+
+    fn searches_invalid_utf8(&self) -> bool {
+        true
+    }
+
+    fn write_stats(&mut self, stats: &SearchStats) {
+        self.stats = *stats;
+    }
+
+    fn finalize(&mut self) {
+        self.end_current_file();
+        self.write_message(&RgMessage::Summary {
+            elapsed_total: self.started.elapsed().into(),
+            stats: RgStats {
+                elapsed: self.elapsed.into(),
+                searches: self.stats.files_searched,
+                searches_with_match: self.files_with_matches,
+                bytes_searched: self.stats.bytes_searched,
+                bytes_printed: self.bytes_printed,
+                matched_lines: self.matched_lines,
+                matches: self.matches,
+            },
+        });
+        let _ = self.stdout.flush();
+    }
+}
+
+/// Offset in `raw` of an offset in its lossy decoding, where each invalid
+/// sequence became one U+FFFD
+fn raw_offset(raw: &[u8], lossy_offset: usize) -> usize {
+    let (mut lossy, mut exact) = (0, 0);
+    for chunk in raw.utf8_chunks() {
+        let valid = chunk.valid().len();
+        if lossy_offset <= lossy + valid {
+            return exact + lossy_offset - lossy;
+        }
+        lossy += valid;
+        exact += valid;
+
+        if !chunk.invalid().is_empty() {
+            if lossy_offset < lossy + char::REPLACEMENT_CHARACTER.len_utf8() {
+                return exact;
+            }
+            lossy += char::REPLACEMENT_CHARACTER.len_utf8();
+            exact += chunk.invalid().len();
+        }
+    }
+    exact
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rg_match_serialisation() {
+        let message = RgMessage::Match {
+            path: RgData::text("src/lib.rs"),
+            lines: RgData::text("fn main() {}\n"),
+            line_number: 3,
+            absolute_offset: 42,
+            submatches: vec![RgSubmatch {
+                matched: RgData::text("main"),
+                start: 3,
+                end: 7,
+            }],
+        };
+
+        let json = serde_json::to_string(&message).unwrap();
+        assert_eq!(
+            json,
+            r#"{"type":"match","data":{"path":{"text":"src/lib.rs"},"lines":{"text":"fn main() {}\n"},"line_number":3,"absolute_offset":42,"submatches":[{"match":{"text":"main"},"start":3,"end":7}]}}"#
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_rg_non_utf8_path_is_bytes() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = Path::new(OsStr::from_bytes(b"caf\xe9.txt"));
+        assert_eq!(
+            RgData::from_path(path),
+            RgData::Bytes {
+                bytes: "Y2Fm6S50eHQ=".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_raw_offsets_of_lossy_lines() {
+        // "caf\xe9 \xff\xfeneedle" decodes to "caf\u{fffd} \u{fffd}\u{fffd}needle"
+        let raw = b"caf\xe9 \xff\xfeneedle";
+        let lossy = String::from_utf8_lossy(raw);
+        let start = lossy.find("needle").unwrap();
+
+        assert_eq!(raw_offset(raw, 0), 0);
+        assert_eq!(raw_offset(raw, 3), 3);
+        assert_eq!(raw_offset(raw, 6), 4);
+        assert_eq!(raw_offset(raw, start), 7);
+        assert_eq!(raw_offset(raw, lossy.len()), raw.len());
+    }
+
+    #[test]
+    fn test_rg_duration_human() {
+        let duration = RgDuration::from(Duration::new(1, 500_000));
+        assert_eq!(duration.human, "1.000500s");
+        assert_eq!((duration.secs, duration.nanos), (1, 500_000));
+    }
+}
@@ -0,0 +1,326 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use super::hyperlink::percent_encode_path;
+use super::{Outputs, SearchMatch};
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const SARIF_VERSION: &str = "2.1.0";
+
+#[derive(Serialize)]
+struct SarifLog<'a> {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    version: &'static str,
+    runs: [SarifRun<'a>; 1],
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifRun<'a> {
+    tool: SarifTool<'a>,
+    invocations: [SarifInvocation<'a>; 1],
+    /// Columns count characters, not UTF-16 code units
+    column_kind: &'static str,
+    results: &'a [SarifResult],
+}
+
+#[derive(Serialize)]
+struct SarifTool<'a> {
+    driver: SarifDriver<'a>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifDriver<'a> {
+    name: &'static str,
+    version: &'static str,
+    information_uri: &'static str,
+    rules: [&'a SarifRule; 1],
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifRule {
+    id: String,
+    short_description: SarifMessage,
+    default_configuration: SarifConfiguration,
+}
+
+#[derive(Serialize)]
+struct SarifConfiguration {
+    level: &'static str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifInvocation<'a> {
+    execution_successful: bool,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    tool_execution_notifications: &'a [SarifNotification],
+}
+
+#[derive(Serialize)]
+struct SarifNotification {
+    level: &'static str,
+    message: SarifMessage,
+    locations: [SarifLocation; 1],
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult {
+    rule_id: String,
+    rule_index: usize,
+    level: &'static str,
+    message: SarifMessage,
+    locations: [SarifLocation; 1],
+}
+
+#[derive(Serialize)]
+struct SarifMessage {
+    text: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifLocation {
+    physical_location: SarifPhysicalLocation,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifPhysicalLocation {
+    artifact_location: SarifArtifactLocation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    region: Option<SarifRegion>,
+}
+
+#[derive(Serialize)]
+struct SarifArtifactLocation {
+    uri: String,
+}
+
+/// Lines and columns are 1-based; `end_column` is the column after the match
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifRegion {
+    start_line: usize,
+    start_column: usize,
+    end_column: usize,
+    snippet: SarifMessage,
+}
+
+/// SARIF 2.1.0 output mode, for code-scanning and code-review tools
+/// Each match becomes a result of a single rule describing the pattern
+pub struct SarifOutput {
+    pattern: String,
+    regex: bool,
+    rule: SarifRule,
+    /// Results with the path and position they're sorted by
+    results: Vec<(PathBuf, usize, usize, SarifResult)>,
+    notifications: Vec<SarifNotification>,
+    sorted: bool,
+}
+
+impl SarifOutput {
+    /// Create an output reporting matches of `pattern` (a regex if `regex` is set)
+    pub fn new(pattern: &str, regex: bool) -> Self {
+        let kind = if regex { "regex" } else { "text" };
+
+        SarifOutput {
+            pattern: pattern.to_string(),
+            regex,
+            rule: SarifRule {
+                id: rule_id(pattern),
+                short_description: SarifMessage {
+                    text: format!("Matches the {} `{}`", kind, pattern),
+                },
+                default_configuration: SarifConfiguration { level: "warning" },
+            },
+            results: Vec::new(),
+            notifications: Vec::new(),
+            sorted: false,
+        }
+    }
+
+    /// Results arrive in a requested order (`--sort`/`--sortr`), so keep it
+    /// rather than sorting them by location
+    pub fn sorted(mut self, sorted: bool) -> Self {
+        self.sorted = sorted;
+        self
+    }
+}
+
+impl Outputs for SarifOutput {
+    fn write_match(&mut self, match_result: &SearchMatch) {
+        let line = match_result.content;
+        let message = |text: &str| {
+            if self.regex {
+                format!("`{}` matches `{}`", text, self.pattern)
+            } else {
+                format!("Found `{}`", text)
+            }
+        };
+
+        // One result per match, so each occurrence can be reviewed separately
+        for &(start, end) in match_result.match_positions {
+            let Some((matched, chars_before)) = match_result.span_text((start, end)) else {
+                continue;
+            };
+            let start_column = chars_before + 1;
+            let result = SarifResult {
+                rule_id: self.rule.id.clone(),
+                rule_index: 0,
+                level: "warning",
+                message: SarifMessage {
+                    text: message(matched),
+                },
+                locations: [location(
+                    match_result.path,
+                    Some(SarifRegion {
+                        start_line: match_result.line_number,
+                        start_column,
+                        end_column: start_column + matched.chars().count(),
+                        snippet: SarifMessage {
+                            text: line.to_string(),
+                        },
+                    }),
+                )],
+            };
+            self.results.push((
+                match_result.path.to_path_buf(),
+                match_result.line_number,
+                start_column,
+                result,
+            ));
+        }
+    }
+
+    fn write_file(&mut self, _path: &Path) {
+        // Results only come from matches
+    }
+
+    fn write_error(&mut self, path: &Path, message: &str) {
+        self.notifications.push(SarifNotification {
+            level: "warning",
+            message: SarifMessage {
+                text: message.to_string(),
+            },
+            locations: [location(path, None)],
+        });
+    }
+
+    fn finalize(&mut self) {
+        // Sort by location for consistent output, unless already in a requested order
+        if !self.sorted {
+            self.results
+                .sort_by(|a, b| (&a.0, a.1, a.2).cmp(&(&b.0, b.1, b.2)));
+        }
+        let results: Vec<SarifResult> = self
+            .results
+            .drain(..)
+            .map(|(_, _, _, result)| result)
+            .collect();
+
+        let log = SarifLog {
+            schema: SARIF_SCHEMA,
+            version: SARIF_VERSION,
+            runs: [SarifRun {
+                tool: SarifTool {
+                    driver: SarifDriver {
+                        name: env!("CARGO_PKG_NAME"),
+                        version: env!("CARGO_PKG_VERSION"),
+                        information_uri: env!("CARGO_PKG_REPOSITORY"),
+                        rules: [&self.rule],
+                    },
+                },
+                invocations: [SarifInvocation {
+                    execution_successful: true,
+                    tool_execution_notifications: &self.notifications,
+                }],
+                column_kind: "unicodeCodePoints",
+                results: &results,
+            }],
+        };
+
+        match serde_json::to_string_pretty(&log) {
+            Ok(json) => println!("{}", json),
+            Err(e) => eprintln!("Error serialising SARIF: {}", e),
+        }
+    }
+}
+
+fn location(path: &Path, region: Option<SarifRegion>) -> SarifLocation {
+    SarifLocation {
+        physical_location: SarifPhysicalLocation {
+            artifact_location: SarifArtifactLocation {
+                uri: artifact_uri(path),
+            },
+            region,
+        },
+    }
+}
+
+/// Rule id derived from the pattern: its letters and digits, lowercased,
+/// with everything else collapsed to single hyphens
+fn rule_id(pattern: &str) -> String {
+    let mut id = String::new();
+    for c in pattern.chars() {
+        if c.is_ascii_alphanumeric() {
+            id.push(c.to_ascii_lowercase());
+        } else if !id.is_empty() && !id.ends_with('-') {
+            id.push('-');
+        }
+    }
+    id.truncate(64);
+    let id = id.trim_end_matches('-');
+
+    if id.is_empty() {
+        "pattern".to_string()
+    } else {
+        id.to_string()
+    }
+}
+
+/// URI reference for a path: relative paths stay relative, absolute paths
+/// become `file://` URIs, and anything outside the unreserved set is
+/// percent-encoded
+fn artifact_uri(path: &Path) -> String {
+    let path = path.strip_prefix(".").unwrap_or(path);
+    let mut uri = String::new();
+    if path.has_root() {
+        uri.push_str("file://");
+        if !path.as_os_str().as_encoded_bytes().starts_with(b"/") {
+            // Windows drive paths, e.g. file:///C:/src
+            uri.push('/');
+        }
+    }
+    percent_encode_path(&mut uri, path);
+    uri
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rule_id() {
+        assert_eq!(rule_id("unwrap()"), "unwrap");
+        assert_eq!(rule_id(r"\bstd::mem::transmute\b"), "bstd-mem-transmute-b");
+        assert_eq!(rule_id("TODO: fix"), "todo-fix");
+        assert_eq!(rule_id("***"), "pattern");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_artifact_uri() {
+        assert_eq!(artifact_uri(Path::new("src/lib.rs")), "src/lib.rs");
+        assert_eq!(artifact_uri(Path::new("./src/lib.rs")), "src/lib.rs");
+        assert_eq!(
+            artifact_uri(Path::new("/tmp/a b#1.rs")),
+            "file:///tmp/a%20b%231.rs"
+        );
+        assert_eq!(artifact_uri(Path::new("café.txt")), "caf%C3%A9.txt");
+    }
+}
@@ -1,78 +1,159 @@
 use std::io::{self, Write};
-use std::path::Path;
-use termcolor::{Color, ColorSpec, StandardStream, WriteColor};
+use std::path::{Path, PathBuf};
+use termcolor::{StandardStream, WriteColor};
 
-use super::{ColourMode, Outputs, SearchMatch};
+use super::hyperlink::{end_hyperlink, start_hyperlink};
+use super::{ColourMode, ColourScheme, HyperlinkFormat, Outputs, SearchMatch, write_path};
 
 /// Standard output format with coloured output
+/// Writes `path:line: content` per match, or with headings, the path once
+/// above each file's `line: content` rows
 pub struct StandardOutput {
     stdout: StandardStream,
-    // Colour specs for different elements
-    path_colour: ColorSpec,
-    line_number_colour: ColorSpec,
-    match_colour: ColorSpec,
+    colours: ColourScheme,
+    null_separated: bool,
+    heading: bool,
+    hyperlink_format: Option<HyperlinkFormat>,
+    /// File whose heading was written last
+    current_file: Option<PathBuf>,
 }
 
 impl StandardOutput {
     pub fn new(colour_mode: ColourMode) -> Self {
         let stdout = StandardStream::stdout(colour_mode.to_color_choice());
 
-        let mut path_colour = ColorSpec::new();
-        path_colour.set_fg(Some(Color::Green));
+        StandardOutput {
+            stdout,
+            colours: ColourScheme::default(),
+            null_separated: false,
+            heading: false,
+            hyperlink_format: None,
+            current_file: None,
+        }
+    }
 
-        let mut line_number_colour = ColorSpec::new();
-        line_number_colour.set_fg(Some(Color::Cyan));
+    /// Use these colours instead of the defaults (`--colors`)
+    pub fn colours(mut self, colours: ColourScheme) -> Self {
+        self.colours = colours;
+        self
+    }
 
-        let mut match_colour = ColorSpec::new();
-        match_colour.set_fg(Some(Color::White));
-        match_colour.set_bg(Some(Color::Blue));
-        match_colour.set_bold(true);
+    /// Follow each path with a NUL byte instead of `:` or a newline (`-0`)
+    pub fn null_separated(mut self, null_separated: bool) -> Self {
+        self.null_separated = null_separated;
+        self
+    }
 
-        StandardOutput {
-            stdout,
-            path_colour,
-            line_number_colour,
-            match_colour,
+    /// Group matches under a heading per file instead of repeating the path (`--heading`)
+    pub fn heading(mut self, heading: bool) -> Self {
+        self.heading = heading;
+        self
+    }
+
+    /// Link paths and line numbers to the matching line with OSC 8
+    /// hyperlinks, when colour is enabled (`--hyperlink-format`)
+    pub fn hyperlinks(mut self, format: Option<HyperlinkFormat>) -> Self {
+        self.hyperlink_format = format;
+        self
+    }
+
+    /// Write the heading for a file if it isn't the current one, with a
+    /// blank line between files
+    fn write_heading(&mut self, path: &Path) -> io::Result<()> {
+        if self.current_file.as_deref() == Some(path) {
+            return Ok(());
+        }
+        if self.current_file.is_some() {
+            writeln!(self.stdout)?;
+        }
+        self.current_file = Some(path.to_path_buf());
+
+        self.write_coloured_path(path, None, None)?;
+        self.write_path_end("\n")
+    }
+
+    /// Write the byte that ends a path, if NUL-separated
+    fn write_path_end(&mut self, default: &str) -> io::Result<()> {
+        if self.null_separated {
+            self.stdout.write_all(b"\0")
+        } else {
+            write!(self.stdout, "{}", default)
         }
     }
 
-    /// Write coloured path
-    fn write_coloured_path(&mut self, path: &Path) -> io::Result<()> {
-        self.stdout.set_color(&self.path_colour)?;
-        write!(self.stdout, "{}", path.to_string_lossy())?;
+    /// Write coloured path, linked to a line and column if hyperlinks are on
+    fn write_coloured_path(
+        &mut self,
+        path: &Path,
+        line: Option<usize>,
+        column: Option<usize>,
+    ) -> io::Result<()> {
+        let linked = start_hyperlink(
+            &mut self.stdout,
+            self.hyperlink_format.as_ref(),
+            path,
+            line,
+            column,
+        )?;
+        self.stdout.set_color(&self.colours.path)?;
+        write_path(&mut self.stdout, path)?;
         self.stdout.reset()?;
+        if linked {
+            end_hyperlink(&mut self.stdout)?;
+        }
         Ok(())
     }
 
-    /// Write coloured line number
-    fn write_coloured_line_number(&mut self, line_num: usize) -> io::Result<()> {
-        self.stdout.set_color(&self.line_number_colour)?;
+    /// Write coloured line number, linked to the line if hyperlinks are on
+    fn write_coloured_line_number(
+        &mut self,
+        path: &Path,
+        line_num: usize,
+        column: usize,
+    ) -> io::Result<()> {
+        let linked = start_hyperlink(
+            &mut self.stdout,
+            self.hyperlink_format.as_ref(),
+            path,
+            Some(line_num),
+            Some(column),
+        )?;
+        self.stdout.set_color(&self.colours.line)?;
         write!(self.stdout, "{}", line_num)?;
         self.stdout.reset()?;
+        if linked {
+            end_hyperlink(&mut self.stdout)?;
+        }
         Ok(())
     }
 
     /// Write content with highlighted matches
-    fn write_highlighted_content(
-        &mut self,
-        content: &str,
-        match_positions: &[(usize, usize)],
-    ) -> io::Result<()> {
-        if match_positions.is_empty() {
+    ///
+    /// Spans that aren't within the line, or that overlap an earlier one,
+    /// are left unhighlighted.
+    fn write_highlighted_content(&mut self, match_result: &SearchMatch) -> io::Result<()> {
+        let content = match_result.content;
+        if match_result.match_positions.is_empty() {
             // No matches to highlight, just write content
             write!(self.stdout, "{}", content)?;
             return Ok(());
         }
 
         let mut last_end = 0;
-        for (start, end) in match_positions {
+        for &(start, end) in match_result.match_positions {
+            let Some((matched, _)) = match_result.span_text((start, end)) else {
+                continue;
+            };
+            if start < last_end {
+                continue;
+            }
             // Write text before match
-            write!(self.stdout, "{}", &content[last_end..*start])?;
+            write!(self.stdout, "{}", &content[last_end..start])?;
             // Write highlighted match
-            self.stdout.set_color(&self.match_colour)?;
-            write!(self.stdout, "{}", &content[*start..*end])?;
+            self.stdout.set_color(&self.colours.matched)?;
+            write!(self.stdout, "{}", matched)?;
             self.stdout.reset()?;
-            last_end = *end;
+            last_end = end;
         }
         // Write remaining text after last match
         write!(self.stdout, "{}", &content[last_end..])?;
@@ -90,18 +171,30 @@ impl Outputs for StandardOutput {
     fn write_match(&mut self, match_result: &SearchMatch) {
         // v3.0.0 standard format: "path:line: content"
         // Matches grep/ripgrep conventions
-        // With colours:
+        // With colours (defaults, see ColourScheme):
         // - Path: green
         // - Line number: cyan
         // - Separators: default
         // - Content: default with highlighted matches (bold white on blue)
 
         // Note: Ignoring errors for now since println! doesn't expose errors either
-        let _ = self.write_coloured_path(match_result.path);
-        let _ = write!(self.stdout, ":");
-        let _ = self.write_coloured_line_number(match_result.line_number);
+        let line = match_result.line_number;
+        // Hyperlinks point at the first match, in characters
+        let column = match_result
+            .match_positions
+            .first()
+            .and_then(|&span| match_result.span_text(span))
+            .map_or(1, |(_, chars_before)| chars_before + 1);
+        if self.heading {
+            // Matches for a file arrive together, so the path is written once
+            let _ = self.write_heading(match_result.path);
+        } else {
+            let _ = self.write_coloured_path(match_result.path, Some(line), Some(column));
+            let _ = self.write_path_end(":");
+        }
+        let _ = self.write_coloured_line_number(match_result.path, line, column);
         let _ = write!(self.stdout, ": ");
-        let _ = self.write_highlighted_content(match_result.content, match_result.match_positions);
+        let _ = self.write_highlighted_content(match_result);
         let _ = writeln!(self.stdout);
         let _ = self.stdout.flush();
     }
@@ -109,8 +202,8 @@ impl Outputs for StandardOutput {
     fn write_file(&mut self, path: &Path) {
         // v3.0.0 format: plain path without quotes
         // With colours: path in green
-        let _ = self.write_coloured_path(path);
-        let _ = writeln!(self.stdout);
+        let _ = self.write_coloured_path(path, None, None);
+        let _ = self.write_path_end("\n");
         let _ = self.stdout.flush();
     }
 }
@@ -0,0 +1,604 @@
+use anyhow::{Error, Result, bail};
+use std::ffi::OsStr;
+use std::fs::{self, Metadata};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use termcolor::{ColorSpec, StandardStream, WriteColor};
+
+use super::colour::parse_colour;
+use super::long::{format_permissions, format_timestamp};
+use super::{ColourMode, ColourScheme, NamedCapture, Outputs, SearchMatch, span_text, write_path};
+
+/// A value a template placeholder is replaced with
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Field {
+    /// `{path}`
+    Path,
+    /// `{name}`: the file name
+    Name,
+    /// `{stem}`: the file name without its extension
+    Stem,
+    /// `{ext}`: the extension, without the dot
+    Extension,
+    /// `{dir}`: the parent directory, `.` for bare file names
+    Dir,
+    /// `{size}`: file size in bytes
+    Size,
+    /// `{mtime}`: modification time as an RFC 3339 UTC timestamp
+    Modified,
+    /// `{perm}`: permission string in ls format
+    Permissions,
+    /// `{line}`: line number, 1-based
+    Line,
+    /// `{col}`: column of the match in characters, 1-based
+    Column,
+    /// `{match}`: the matched text
+    Match,
+    /// `{content}`: the whole matching line
+    Content,
+    /// `{capture:NAME}`: text of a named capture group, empty if it didn't take part
+    Capture(String),
+    /// `{count}`: number of matches in the file
+    Count,
+    /// `{lines}`: number of matching lines in the file
+    Lines,
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Self> {
+        let field = match name {
+            "path" => Field::Path,
+            "name" => Field::Name,
+            "stem" => Field::Stem,
+            "ext" => Field::Extension,
+            "dir" => Field::Dir,
+            "size" => Field::Size,
+            "mtime" => Field::Modified,
+            "perm" => Field::Permissions,
+            "line" => Field::Line,
+            "col" => Field::Column,
+            "match" => Field::Match,
+            "content" => Field::Content,
+            "count" => Field::Count,
+            "lines" => Field::Lines,
+            _ => {
+                let group = name.strip_prefix("capture:")?;
+                if group.is_empty() {
+                    return None;
+                }
+                Field::Capture(group.to_string())
+            }
+        };
+        Some(field)
+    }
+
+    /// Whether the field describes a single match rather than the file
+    fn is_match_field(&self) -> bool {
+        matches!(
+            self,
+            Field::Line | Field::Column | Field::Match | Field::Content | Field::Capture(_)
+        )
+    }
+
+    /// Whether the field needs a separate entry for each match on a line
+    fn is_per_match(&self) -> bool {
+        matches!(self, Field::Column | Field::Match | Field::Capture(_))
+    }
+}
+
+/// A colour directive: one of the scheme's colours, a foreground colour, or reset
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Directive {
+    Path,
+    Line,
+    Match,
+    Colour(ColorSpec),
+    Reset,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Piece {
+    Text(String),
+    Field(Field),
+    Colour(Directive),
+}
+
+/// An output template (`--format`, `--format-header`, `--format-footer`)
+///
+/// Text is copied as is, apart from `{placeholder}`s, `{{`/`}}` for
+/// literal braces and the escapes `\n`, `\t` and `\\`. Colour directives
+/// `{colour:path}`, `{colour:line}` and `{colour:match}` switch to the
+/// colour scheme's colours, `{colour:VALUE}` to any foreground colour
+/// accepted by `--colors`, and `{colour:reset}` back to plain text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    pieces: Vec<Piece>,
+}
+
+impl FromStr for Template {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut pieces = Vec::new();
+        let mut text = String::new();
+        let mut chars = s.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '}' => bail!(
+                    "unmatched '}}' in template '{}' (use '}}}}' for a literal brace)",
+                    s
+                ),
+                '\\' => match chars.next() {
+                    Some('n') => text.push('\n'),
+                    Some('t') => text.push('\t'),
+                    Some('\\') => text.push('\\'),
+                    Some(other) => {
+                        text.push('\\');
+                        text.push(other);
+                    }
+                    None => text.push('\\'),
+                },
+                '{' => {
+                    let mut name = String::new();
+                    let mut closed = false;
+                    for c in chars.by_ref() {
+                        if c == '}' {
+                            closed = true;
+                            break;
+                        }
+                        name.push(c);
+                    }
+                    if !closed {
+                        bail!("unclosed '{{' in template '{}'", s);
+                    }
+                    if !text.is_empty() {
+                        pieces.push(Piece::Text(std::mem::take(&mut text)));
+                    }
+                    pieces.push(parse_placeholder(&name)?);
+                }
+                _ => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            pieces.push(Piece::Text(text));
+        }
+
+        Ok(Template { pieces })
+    }
+}
+
+fn parse_placeholder(name: &str) -> Result<Piece> {
+    if let Some(colour) = name
+        .strip_prefix("colour:")
+        .or_else(|| name.strip_prefix("color:"))
+    {
+        let directive = match colour {
+            "path" => Directive::Path,
+            "line" => Directive::Line,
+            "match" => Directive::Match,
+            "reset" => Directive::Reset,
+            _ => {
+                let mut spec = ColorSpec::new();
+                spec.set_fg(Some(parse_colour(colour)?));
+                Directive::Colour(spec)
+            }
+        };
+        return Ok(Piece::Colour(directive));
+    }
+
+    match Field::parse(name) {
+        Some(field) => Ok(Piece::Field(field)),
+        None => bail!(
+            "unknown placeholder {{{}}} (expected path, name, stem, ext, dir, size, mtime, perm, line, col, match, content, capture:NAME, count, lines or colour:...)",
+            name
+        ),
+    }
+}
+
+impl Template {
+    fn fields(&self) -> impl Iterator<Item = &Field> {
+        self.pieces.iter().filter_map(|piece| match piece {
+            Piece::Field(field) => Some(field),
+            _ => None,
+        })
+    }
+
+    /// Whether the template uses placeholders that describe a match, so
+    /// only applies when searching
+    pub fn uses_match_fields(&self) -> bool {
+        self.fields().any(Field::is_match_field)
+    }
+
+    /// Whether the template uses `{count}` or `{lines}`, which are only
+    /// known once a file's matches have all been written
+    pub fn uses_count_fields(&self) -> bool {
+        self.fields()
+            .any(|field| matches!(field, Field::Count | Field::Lines))
+    }
+
+    /// Whether the template needs an entry per match rather than per line
+    fn is_per_match(&self) -> bool {
+        self.fields().any(Field::is_per_match)
+    }
+}
+
+/// A match being rendered: the line and one match span on it
+struct MatchContext<'a> {
+    line_number: usize,
+    content: &'a str,
+    span: Option<(usize, usize)>,
+    captures: &'a [NamedCapture],
+}
+
+/// State of the file currently being written
+struct FileContext {
+    path: PathBuf,
+    metadata: Option<Metadata>,
+    matches: usize,
+    lines: usize,
+}
+
+/// Templated output mode (`--format`)
+/// Renders a template per match (or per matching line if it doesn't use
+/// `{col}`, `{match}` or captures), or per file when listing, with optional
+/// header and footer templates around each file's matches
+pub struct TemplateOutput {
+    stdout: StandardStream,
+    template: Template,
+    header: Option<Template>,
+    footer: Option<Template>,
+    colours: ColourScheme,
+    current: Option<FileContext>,
+    /// Whether any template reads file metadata
+    needs_metadata: bool,
+}
+
+impl TemplateOutput {
+    pub fn new(template: Template, colour_mode: ColourMode) -> Self {
+        let needs_metadata = needs_metadata(&template);
+        TemplateOutput {
+            stdout: StandardStream::stdout(colour_mode.to_color_choice()),
+            template,
+            header: None,
+            footer: None,
+            colours: ColourScheme::default(),
+            current: None,
+            needs_metadata,
+        }
+    }
+
+    /// Write a template before each file's matches (`--format-header`)
+    pub fn header(mut self, header: Option<Template>) -> Self {
+        self.needs_metadata |= header.as_ref().is_some_and(needs_metadata);
+        self.header = header;
+        self
+    }
+
+    /// Write a template after each file's matches (`--format-footer`)
+    pub fn footer(mut self, footer: Option<Template>) -> Self {
+        self.needs_metadata |= footer.as_ref().is_some_and(needs_metadata);
+        self.footer = footer;
+        self
+    }
+
+    /// Use these colours for `{colour:path}`, `{colour:line}` and
+    /// `{colour:match}` instead of the defaults (`--colors`)
+    pub fn colours(mut self, colours: ColourScheme) -> Self {
+        self.colours = colours;
+        self
+    }
+
+    /// Start a new file, finishing the previous one
+    fn enter_file(&mut self, path: &Path) {
+        if self
+            .current
+            .as_ref()
+            .is_some_and(|file| file.path.as_path() == path)
+        {
+            return;
+        }
+        self.end_file();
+
+        self.current = Some(FileContext {
+            path: path.to_path_buf(),
+            metadata: self
+                .needs_metadata
+                .then(|| fs::symlink_metadata(path).ok())
+                .flatten(),
+            matches: 0,
+            lines: 0,
+        });
+        if let (Some(header), Some(file)) = (&self.header, &self.current) {
+            let _ = render(&mut self.stdout, &self.colours, header, file, None);
+        }
+    }
+
+    /// Write the footer of the current file, if there is one
+    fn end_file(&mut self) {
+        let Some(file) = self.current.take() else {
+            return;
+        };
+        if let Some(footer) = &self.footer {
+            let _ = render(&mut self.stdout, &self.colours, footer, &file, None);
+        }
+        // Flush once per file, so results appear as each file finishes
+        let _ = self.stdout.flush();
+    }
+}
+
+impl Outputs for TemplateOutput {
+    fn write_match(&mut self, match_result: &SearchMatch) {
+        self.enter_file(match_result.path);
+
+        let Some(file) = &mut self.current else {
+            return;
+        };
+        let positions = match_result.match_positions;
+        file.matches += positions.len();
+        file.lines += 1;
+
+        let spans: Vec<Option<(usize, usize)>> = if self.template.is_per_match() {
+            positions.iter().copied().map(Some).collect()
+        } else {
+            // One entry for the whole line
+            vec![positions.first().copied()]
+        };
+        for (index, span) in spans.into_iter().enumerate() {
+            let context = MatchContext {
+                line_number: match_result.line_number,
+                content: match_result.content,
+                span,
+                captures: match_result
+                    .captures
+                    .get(index)
+                    .map_or(&[], |captures| captures.as_slice()),
+            };
+            let _ = render(
+                &mut self.stdout,
+                &self.colours,
+                &self.template,
+                file,
+                Some(&context),
+            );
+        }
+    }
+
+    fn write_file(&mut self, path: &Path) {
+        self.enter_file(path);
+        if let Some(file) = &self.current {
+            let _ = render(&mut self.stdout, &self.colours, &self.template, file, None);
+        }
+    }
+
+    fn finalize(&mut self) {
+        self.end_file();
+        let _ = self.stdout.flush();
+    }
+}
+
+/// Render a template for a file, and a match if given, followed by a newline
+fn render(
+    out: &mut StandardStream,
+    colours: &ColourScheme,
+    template: &Template,
+    file: &FileContext,
+    context: Option<&MatchContext>,
+) -> io::Result<()> {
+    let mut coloured = false;
+
+    for piece in &template.pieces {
+        match piece {
+            Piece::Text(text) => out.write_all(text.as_bytes())?,
+            Piece::Colour(directive) => {
+                let spec = match directive {
+                    Directive::Path => &colours.path,
+                    Directive::Line => &colours.line,
+                    Directive::Match => &colours.matched,
+                    Directive::Colour(spec) => spec,
+                    Directive::Reset => {
+                        out.reset()?;
+                        coloured = false;
+                        continue;
+                    }
+                };
+                out.set_color(spec)?;
+                coloured = true;
+            }
+            Piece::Field(field) => write_field(out, field, file, context)?,
+        }
+    }
+
+    if coloured {
+        out.reset()?;
+    }
+    writeln!(out)
+}
+
+fn needs_metadata(template: &Template) -> bool {
+    template
+        .fields()
+        .any(|field| matches!(field, Field::Size | Field::Modified | Field::Permissions))
+}
+
+/// Write the value of a field; match fields are empty outside a match
+fn write_field(
+    out: &mut impl Write,
+    field: &Field,
+    file: &FileContext,
+    context: Option<&MatchContext>,
+) -> io::Result<()> {
+    let path = file.path.as_path();
+    let metadata = file.metadata.as_ref();
+    let matched = context.and_then(|context| span_text(context.content, context.span?));
+
+    match field {
+        Field::Path => write_path(out, path),
+        Field::Name => write_os_str(out, path.file_name()),
+        Field::Stem => write_os_str(out, path.file_stem()),
+        Field::Extension => write_os_str(out, path.extension()),
+        Field::Dir => match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => write_path(out, parent),
+            _ => out.write_all(b"."),
+        },
+        Field::Size => match metadata {
+            Some(metadata) => write!(out, "{}", metadata.len()),
+            None => Ok(()),
+        },
+        Field::Modified => match metadata.and_then(|metadata| metadata.modified().ok()) {
+            Some(modified) => out.write_all(format_timestamp(modified).as_bytes()),
+            None => Ok(()),
+        },
+        Field::Permissions => match metadata {
+            Some(metadata) => out.write_all(format_permissions(metadata).as_bytes()),
+            None => Ok(()),
+        },
+        Field::Line => match context {
+            Some(context) => write!(out, "{}", context.line_number),
+            None => Ok(()),
+        },
+        Field::Column => match matched {
+            Some((_, chars_before)) => write!(out, "{}", chars_before + 1),
+            None => Ok(()),
+        },
+        Field::Match => match matched {
+            Some((text, _)) => out.write_all(text.as_bytes()),
+            None => Ok(()),
+        },
+        Field::Content => match context {
+            Some(context) => out.write_all(context.content.as_bytes()),
+            None => Ok(()),
+        },
+        Field::Capture(name) => {
+            let text = context.and_then(|context| {
+                let capture = context
+                    .captures
+                    .iter()
+                    .find(|capture| &capture.name == name)?;
+                let (text, _) = span_text(context.content, capture.span?)?;
+                Some(text)
+            });
+            out.write_all(text.unwrap_or_default().as_bytes())
+        }
+        Field::Count => write!(out, "{}", file.matches),
+        Field::Lines => write!(out, "{}", file.lines),
+    }
+}
+
+fn write_os_str(out: &mut impl Write, part: Option<&OsStr>) -> io::Result<()> {
+    match part {
+        Some(part) => write_path(out, Path::new(part)),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_template() -> Result<()> {
+        let template: Template =
+            r"{path}:{line}\t{{x}} {colour:match}{match}{colour:reset}".parse()?;
+        assert_eq!(
+            template.pieces,
+            vec![
+                Piece::Field(Field::Path),
+                Piece::Text(":".to_string()),
+                Piece::Field(Field::Line),
+                Piece::Text("\t{x} ".to_string()),
+                Piece::Colour(Directive::Match),
+                Piece::Field(Field::Match),
+                Piece::Colour(Directive::Reset),
+            ]
+        );
+        assert!(template.uses_match_fields());
+        assert!(template.is_per_match());
+        assert!(!template.uses_count_fields());
+
+        let template: Template = "{capture:name} {color:#ff8800}".parse()?;
+        assert_eq!(
+            template.pieces[0],
+            Piece::Field(Field::Capture("name".to_string()))
+        );
+
+        let template: Template = "{dir}/{stem}.{ext} {count}".parse()?;
+        assert!(!template.uses_match_fields());
+        assert!(template.uses_count_fields());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_templates() {
+        for template in ["{nope}", "{path", "path}", "{colour:mauve}", "{capture:}"] {
+            assert!(template.parse::<Template>().is_err(), "{}", template);
+        }
+    }
+
+    #[test]
+    fn test_write_fields() -> Result<()> {
+        let file = FileContext {
+            path: PathBuf::from("src/lib.rs"),
+            metadata: None,
+            matches: 3,
+            lines: 2,
+        };
+        let captures = [NamedCapture {
+            name: "word".to_string(),
+            span: Some((3, 7)),
+        }];
+        let context = MatchContext {
+            line_number: 12,
+            content: "é: main()",
+            span: Some((4, 8)),
+            captures: &captures,
+        };
+
+        let render = |template: &str, context: Option<&MatchContext>| -> Result<String> {
+            let template: Template = template.parse()?;
+            let mut out = Vec::new();
+            for field in template.fields() {
+                write_field(&mut out, field, &file, context)?;
+                out.push(b'|');
+            }
+            Ok(String::from_utf8(out)?)
+        };
+
+        assert_eq!(
+            render("{path}{name}{stem}{ext}{dir}", None)?,
+            "src/lib.rs|lib.rs|lib|rs|src|"
+        );
+        assert_eq!(
+            render(
+                "{line}{col}{match}{capture:word}{capture:other}",
+                Some(&context)
+            )?,
+            "12|4|main| mai||"
+        );
+        assert_eq!(render("{line}{match}{count}{lines}", None)?, "||3|2|");
+
+        // Spans that aren't within the line write nothing
+        let captures = [NamedCapture {
+            name: "word".to_string(),
+            span: Some((4, 20)),
+        }];
+        let broken = MatchContext {
+            span: Some((1, 3)),
+            captures: &captures,
+            ..context
+        };
+        assert_eq!(render("{col}{match}{capture:word}", Some(&broken))?, "|||");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,121 @@
+use std::io::{self, BufWriter, Stdout, Write};
+use std::path::{Path, PathBuf};
+
+use super::{Outputs, SearchMatch, write_path};
+
+/// Vim/Emacs quickfix format (like rg --vimgrep)
+/// Outputs `path:line:column:content` once per match, never coloured.
+/// Columns are 1-based byte offsets, as Vim's `%c` expects.
+pub struct VimgrepOutput {
+    stdout: BufWriter<Stdout>,
+    current_file: Option<PathBuf>,
+}
+
+impl VimgrepOutput {
+    pub fn new() -> Self {
+        VimgrepOutput {
+            stdout: BufWriter::new(io::stdout()),
+            current_file: None,
+        }
+    }
+}
+
+impl Default for VimgrepOutput {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Outputs for VimgrepOutput {
+    fn write_match(&mut self, match_result: &SearchMatch) {
+        // Flush once per file, so entries appear as each file finishes
+        if self.current_file.as_deref() != Some(match_result.path) {
+            let _ = self.stdout.flush();
+            self.current_file = Some(match_result.path.to_path_buf());
+        }
+
+        let _ = write_entries(&mut self.stdout, match_result);
+    }
+
+    fn write_file(&mut self, path: &Path) {
+        let _ = write_path(&mut self.stdout, path);
+        let _ = writeln!(self.stdout);
+    }
+
+    fn finalize(&mut self) {
+        let _ = self.stdout.flush();
+    }
+}
+
+/// Write one entry per match, skipping spans that aren't within the line
+fn write_entries(out: &mut impl Write, match_result: &SearchMatch) -> io::Result<()> {
+    for &(start, end) in match_result.match_positions {
+        if match_result.span_text((start, end)).is_none() {
+            continue;
+        }
+        write_path(out, match_result.path)?;
+        writeln!(
+            out,
+            ":{}:{}:{}",
+            match_result.line_number,
+            start + 1,
+            match_result.content
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::searcher::{Searcher, Searches};
+
+    fn entries(content: &str, match_positions: &[(usize, usize)]) -> String {
+        let mut out = Vec::new();
+        write_entries(
+            &mut out,
+            &SearchMatch {
+                path: Path::new("src/lib.rs"),
+                line_number: 3,
+                byte_offset: 0,
+                content,
+                match_positions,
+                captures: &[],
+                raw_content: None,
+                line_ending: "\n",
+            },
+        )
+        .unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_one_entry_per_match() {
+        assert_eq!(
+            entries("todo: x, todo: y", &[(0, 4), (9, 13)]),
+            "src/lib.rs:3:1:todo: x, todo: y\nsrc/lib.rs:3:10:todo: x, todo: y\n"
+        );
+    }
+
+    #[test]
+    fn test_multibyte_columns_are_bytes() {
+        assert_eq!(
+            entries("café todo", &[(6, 10)]),
+            "src/lib.rs:3:7:café todo\n"
+        );
+
+        // Case-insensitive spans index the original line, even where
+        // lowercasing changes its length
+        let line = "İİ NEEDLE x";
+        let result = Searcher::new("needle", true).search_line(line, 3).unwrap();
+        assert_eq!(
+            entries(line, &result.match_positions),
+            "src/lib.rs:3:6:İİ NEEDLE x\n"
+        );
+    }
+
+    #[test]
+    fn test_spans_outside_the_line_are_skipped() {
+        assert_eq!(entries("İİ", &[(1, 3), (4, 9)]), "");
+    }
+}
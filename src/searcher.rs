@@ -7,19 +7,32 @@ pub struct SearchResult {
     pub rownum: usize,
     pub line: String,
     pub match_positions: Vec<(usize, usize)>, // (start, end) byte positions of matches
+    /// Named capture groups for each match, in the same order as
+    /// `match_positions`; empty unless the pattern has named groups
+    pub captures: Vec<Vec<NamedCapture>>,
+}
+
+/// A named capture group within a regex match
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamedCapture {
+    pub name: String,
+    /// (start, end) byte positions in the line, or None if the group didn't participate
+    pub span: Option<(usize, usize)>,
 }
 
 // Structs for basic and regex searchers
 pub struct Searcher<'a> {
     query: &'a str,
-    case_insensitive: bool,
-    /// Cached lowercased query for case-insensitive searches
-    /// Computed once in new() to avoid repeated allocations
-    lowercased_query: Option<String>,
+    /// Escaped `(?i)` pattern for case-insensitive searches, compiled once in
+    /// new(); matching the original line keeps positions valid for it, which
+    /// offsets into a lowercased copy are not when lowercasing changes lengths
+    case_insensitive_pattern: Option<Regex>,
 }
 
 pub struct ReSearcher {
     pattern: Regex,
+    /// Whether the pattern has named groups worth reporting
+    named_groups: bool,
 }
 
 // Searches trait for things which can perform search functions
@@ -34,59 +47,70 @@ impl SearchResult {
             rownum,
             line,
             match_positions,
+            captures: Vec::new(),
         }
     }
 }
 
 impl Searcher<'_> {
     pub fn new(query: &str, case_insensitive: bool) -> Searcher<'_> {
-        let lowercased_query = if case_insensitive {
-            Some(query.to_lowercase())
-        } else {
-            None
-        };
+        let case_insensitive_pattern = case_insensitive.then(|| {
+            Regex::new(&format!("(?i){}", regex::escape(query)))
+                .expect("an escaped query is a valid regex")
+        });
 
         Searcher {
             query,
-            case_insensitive,
-            lowercased_query,
+            case_insensitive_pattern,
         }
     }
 
-    fn find_match_positions(&self, line: &str, case_insensitive: bool) -> Vec<(usize, usize)> {
-        let mut positions = Vec::new();
-        let search_line = if case_insensitive {
-            line.to_lowercase()
-        } else {
-            line.to_string()
-        };
-        // Use cached lowercased query instead of computing it every time
-        let search_query = if case_insensitive {
-            self.lowercased_query.as_ref().unwrap().as_str()
-        } else {
-            self.query
-        };
-
-        let mut start = 0;
-        while let Some(pos) = search_line[start..].find(search_query) {
-            let match_start = start + pos;
-            let match_end = match_start + self.query.len();
-            positions.push((match_start, match_end));
-            start = match_end;
+    fn find_match_positions(&self, line: &str) -> Vec<(usize, usize)> {
+        if let Some(pattern) = &self.case_insensitive_pattern {
+            return pattern
+                .find_iter(line)
+                .map(|m| (m.start(), m.end()))
+                .collect();
         }
 
-        positions
+        line.match_indices(self.query)
+            .map(|(start, matched)| (start, start + matched.len()))
+            .collect()
     }
 }
 
 impl ReSearcher {
     pub fn new(pattern: &str) -> Result<ReSearcher> {
+        let pattern =
+            Regex::new(pattern).context(format!("compiling regex pattern '{}'", pattern))?;
+        let named_groups = pattern.capture_names().flatten().next().is_some();
+
         Ok(ReSearcher {
-            pattern: Regex::new(pattern)
-                .context(format!("compiling regex pattern '{}'", pattern))?,
+            pattern,
+            named_groups,
         })
     }
 
+    /// Named capture groups of each match; only run when the pattern has any
+    /// since capturing is slower than finding
+    fn find_named_captures(&self, line: &str) -> Vec<Vec<NamedCapture>> {
+        self.pattern
+            .captures_iter(line)
+            .map(|captures| {
+                self.pattern
+                    .capture_names()
+                    .enumerate()
+                    .filter_map(|(index, name)| {
+                        Some(NamedCapture {
+                            name: name?.to_string(),
+                            span: captures.get(index).map(|m| (m.start(), m.end())),
+                        })
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
     fn find_regex_match_positions(&self, line: &str) -> Vec<(usize, usize)> {
         self.pattern
             .find_iter(line)
@@ -97,17 +121,13 @@ impl ReSearcher {
 
 impl Searches for Searcher<'_> {
     fn search_line(&self, line: &str, rownum: usize) -> Option<SearchResult> {
-        // Use cached lowercased query instead of computing it every time
-        let matches = if self.case_insensitive {
-            let lowercased_line = line.to_lowercase();
-            let lowercased_query = self.lowercased_query.as_ref().unwrap();
-            lowercased_line.contains(lowercased_query)
-        } else {
-            line.contains(self.query)
+        let matches = match &self.case_insensitive_pattern {
+            Some(pattern) => pattern.is_match(line),
+            None => line.contains(self.query),
         };
 
         if matches {
-            let match_positions = self.find_match_positions(line, self.case_insensitive);
+            let match_positions = self.find_match_positions(line);
             Some(SearchResult::new(rownum, line.to_string(), match_positions))
         } else {
             None
@@ -119,7 +139,11 @@ impl Searches for ReSearcher {
     fn search_line(&self, line: &str, rownum: usize) -> Option<SearchResult> {
         if self.pattern.is_match(line) {
             let match_positions = self.find_regex_match_positions(line);
-            Some(SearchResult::new(rownum, line.to_string(), match_positions))
+            let mut result = SearchResult::new(rownum, line.to_string(), match_positions);
+            if self.named_groups {
+                result.captures = self.find_named_captures(line);
+            }
+            Some(result)
         } else {
             None
         }
@@ -173,6 +197,20 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_case_insensitive_positions_in_original_line() {
+        // Lowercasing 'İ' takes 3 bytes instead of 2, so positions must come
+        // from the original line
+        let searcher = Searcher::new("needle", true);
+
+        let line = "İİ NEEDLE x needle";
+        let result = searcher.search_line(line, 1).unwrap();
+        assert_eq!(result.match_positions, vec![(5, 11), (14, 20)]);
+        for &(start, end) in &result.match_positions {
+            assert!(line[start..end].eq_ignore_ascii_case("needle"));
+        }
+    }
+
     #[test]
     fn test_regex_match() -> Result<(), Error> {
         let re_searcher = ReSearcher::new("[a-z]+").expect("Valid regex pattern");
@@ -273,4 +311,30 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_named_captures() {
+        let re_searcher = ReSearcher::new(r"(?P<key>\w+)=(?P<value>\d+)?").unwrap();
+
+        let result = re_searcher.search_line("a=1 b=", 1).unwrap();
+        assert_eq!(result.match_positions, vec![(0, 3), (4, 6)]);
+        assert_eq!(
+            result.captures[0],
+            vec![
+                NamedCapture {
+                    name: "key".to_string(),
+                    span: Some((0, 1))
+                },
+                NamedCapture {
+                    name: "value".to_string(),
+                    span: Some((2, 3))
+                },
+            ]
+        );
+        assert_eq!(result.captures[1][1].span, None);
+
+        // Unnamed groups aren't reported
+        let unnamed = ReSearcher::new("(a)").unwrap();
+        assert!(unnamed.search_line("abc", 1).unwrap().captures.is_empty());
+    }
 }
@@ -0,0 +1,142 @@
+use anyhow::{Error, Result, bail};
+use rayon::prelude::*;
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::UNIX_EPOCH;
+
+/// What results are sorted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    /// File path, lexicographically
+    Path,
+    /// Last modification time, oldest first
+    Modified,
+    /// File size, smallest first
+    Size,
+    /// Number of matching lines, fewest first
+    Matches,
+}
+
+/// A sort key and direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SortOrder {
+    pub key: SortKey,
+    pub reverse: bool,
+}
+
+impl FromStr for SortKey {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "path" => Ok(SortKey::Path),
+            "mtime" | "modified" => Ok(SortKey::Modified),
+            "size" => Ok(SortKey::Size),
+            "matches" => Ok(SortKey::Matches),
+            _ => bail!(
+                "unknown sort key '{}' (expected path, mtime, size or matches)",
+                s
+            ),
+        }
+    }
+}
+
+impl SortOrder {
+    pub fn new(key: SortKey, reverse: bool) -> Self {
+        SortOrder { key, reverse }
+    }
+
+    /// Apply the direction to an ascending comparison
+    pub(crate) fn direct(self, ordering: Ordering) -> Ordering {
+        if self.reverse {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+}
+
+/// Sort paths by a file-level key, breaking ties by path
+///
+/// `SortKey::Matches` isn't known until files are searched, so here it
+/// sorts by path; `search_files_sorted` reorders by match count afterwards.
+pub fn sort_paths(paths: Vec<PathBuf>, order: SortOrder) -> Vec<PathBuf> {
+    let mut keyed: Vec<(u128, PathBuf)> = match order.key {
+        SortKey::Path | SortKey::Matches => paths.into_iter().map(|p| (0, p)).collect(),
+        // Look up metadata in parallel, as this is a stat call per file
+        SortKey::Modified => paths
+            .into_par_iter()
+            .map(|p| (modified_key(&p), p))
+            .collect(),
+        SortKey::Size => paths
+            .into_par_iter()
+            .map(|p| (p.metadata().map_or(0, |m| m.len() as u128), p))
+            .collect(),
+    };
+
+    keyed.par_sort_by(|(a_key, a_path), (b_key, b_path)| {
+        order.direct(a_key.cmp(b_key).then_with(|| a_path.cmp(b_path)))
+    });
+    keyed.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Modification time as nanoseconds since the epoch (0 if unavailable)
+fn modified_key(path: &Path) -> u128 {
+    path.metadata()
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_nanos())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_sort_keys() -> Result<()> {
+        assert_eq!("path".parse::<SortKey>()?, SortKey::Path);
+        assert_eq!("mtime".parse::<SortKey>()?, SortKey::Modified);
+        assert_eq!("size".parse::<SortKey>()?, SortKey::Size);
+        assert_eq!("matches".parse::<SortKey>()?, SortKey::Matches);
+        assert!("name".parse::<SortKey>().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn sort_paths_by_path() {
+        let paths = vec![
+            PathBuf::from("b.txt"),
+            PathBuf::from("c.txt"),
+            PathBuf::from("a.txt"),
+        ];
+
+        let sorted = sort_paths(paths.clone(), SortOrder::new(SortKey::Path, false));
+        assert_eq!(
+            sorted,
+            ["a.txt", "b.txt", "c.txt"]
+                .iter()
+                .map(PathBuf::from)
+                .collect::<Vec<_>>()
+        );
+
+        let reversed = sort_paths(paths, SortOrder::new(SortKey::Path, true));
+        assert_eq!(
+            reversed,
+            ["c.txt", "b.txt", "a.txt"]
+                .iter()
+                .map(PathBuf::from)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn sort_paths_by_size() {
+        let paths = vec![PathBuf::from("Cargo.toml"), PathBuf::from("Cargo.lock")];
+
+        let sorted = sort_paths(paths, SortOrder::new(SortKey::Size, true));
+        assert_eq!(sorted[0], PathBuf::from("Cargo.lock"));
+    }
+}
@@ -1,5 +1,33 @@
+use std::path::Path;
 use std::process::Command;
 
+/// Temporary directory of test files, removed when dropped
+struct Fixture {
+    dir: tempfile::TempDir,
+}
+
+impl Fixture {
+    fn new() -> Fixture {
+        let dir = tempfile::Builder::new()
+            .prefix("finders_cli")
+            .tempdir()
+            .unwrap();
+        Fixture { dir }
+    }
+
+    /// Add a file, creating its parent directories
+    fn file(self, name: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> Fixture {
+        let path = self.dir.path().join(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+        self
+    }
+
+    fn path(&self) -> &Path {
+        self.dir.path()
+    }
+}
+
 /// Test that invalid regex patterns display user-friendly errors
 #[test]
 fn test_invalid_regex_pattern_cli() {
@@ -74,3 +102,1162 @@ fn test_valid_regex_pattern_cli() {
         stdout
     );
 }
+
+/// Test that metadata filters are parsed and reported with context
+#[test]
+fn test_invalid_size_filter_cli() {
+    let output = Command::new(env!("CARGO_BIN_EXE_finder"))
+        .arg(".")
+        .arg("--size")
+        .arg("+10X")
+        .output()
+        .expect("Failed to execute finder");
+
+    assert!(!output.status.success());
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("parsing file filters"),
+        "Expected context message, got: {}",
+        stderr
+    );
+}
+
+/// Test that metadata filters compose with the file pattern
+#[test]
+fn test_size_filter_cli() {
+    let output = Command::new(env!("CARGO_BIN_EXE_finder"))
+        .arg(".")
+        .arg("--file-pattern")
+        .arg("Cargo.toml")
+        .arg("--size")
+        .arg("-1M")
+        .arg("--changed-before")
+        .arg("0s")
+        .output()
+        .expect("Failed to execute finder");
+
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Cargo.toml"),
+        "Should find Cargo.toml, got: {}",
+        stdout
+    );
+}
+
+/// Test that --type-list includes user-defined types
+#[test]
+fn test_type_list_cli() {
+    let output = Command::new(env!("CARGO_BIN_EXE_finder"))
+        .arg("--type-add")
+        .arg("proto:*.proto")
+        .arg("--type-list")
+        .output()
+        .expect("Failed to execute finder");
+
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("rust: *.rs"), "got: {}", stdout);
+    assert!(stdout.contains("proto: *.proto"), "got: {}", stdout);
+}
+
+/// Test that unknown file types are rejected with context
+#[test]
+fn test_unknown_type_cli() {
+    let output = Command::new(env!("CARGO_BIN_EXE_finder"))
+        .arg(".")
+        .arg("--type")
+        .arg("nonexistent")
+        .output()
+        .expect("Failed to execute finder");
+
+    assert!(!output.status.success());
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("unrecognised file type 'nonexistent'"),
+        "Expected type error, got: {}",
+        stderr
+    );
+}
+
+/// Test that symlink loops and broken links are reported in verbose mode
+#[cfg(unix)]
+#[test]
+fn test_symlink_warnings_cli() {
+    use std::os::unix::fs::symlink;
+
+    let fixture = Fixture::new();
+    let dir = fixture.path();
+    std::fs::create_dir(dir.join("sub")).unwrap();
+    symlink(dir, dir.join("sub/loop")).unwrap();
+    symlink(dir.join("missing"), dir.join("broken")).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_finder"))
+        .arg(dir)
+        .arg("--verbose")
+        .output()
+        .expect("Failed to execute finder");
+
+    assert!(output.status.success());
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Symlink loop"), "got: {}", stderr);
+    assert!(stderr.contains("Broken symlink"), "got: {}", stderr);
+}
+
+/// Test that JSON output merges results from several roots
+#[test]
+fn test_multiple_roots_json_cli() {
+    let output = Command::new(env!("CARGO_BIN_EXE_finder"))
+        .arg("src/file_finder")
+        .arg("src/output")
+        .arg("src")
+        .arg("--regex-pattern")
+        .arg("^pub struct")
+        .arg("--json")
+        .output()
+        .expect("Failed to execute finder");
+
+    assert!(output.status.success());
+
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("Output should be valid JSON");
+    let paths: Vec<&str> = json["files"]
+        .as_array()
+        .expect("Output should be an array")
+        .iter()
+        .map(|file| file["path"].as_str().unwrap())
+        .collect();
+
+    assert!(
+        paths.iter().any(|p| p.ends_with("mod.rs")),
+        "got: {:?}",
+        paths
+    );
+    assert!(
+        paths.iter().any(|p| p.ends_with("standard.rs")),
+        "got: {:?}",
+        paths
+    );
+
+    let mut unique = paths.clone();
+    unique.sort();
+    unique.dedup();
+    assert_eq!(unique.len(), paths.len(), "Duplicate paths: {:?}", paths);
+}
+
+/// Test that a hidden root inside another root is still walked
+#[test]
+fn test_hidden_nested_root_cli() {
+    let output = Command::new(env!("CARGO_BIN_EXE_finder"))
+        .arg(".")
+        .arg(".github")
+        .arg("--file-pattern")
+        .arg("ci.yml")
+        .output()
+        .expect("Failed to execute finder");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("ci.yml"), "got: {}", stdout);
+}
+
+/// Test that --one-file-system reports the mount points it skips
+#[cfg(target_os = "linux")]
+#[test]
+fn test_one_file_system_cli() {
+    use std::os::unix::fs::MetadataExt;
+
+    // Only meaningful where /proc is mounted separately from /
+    let (Ok(root), Ok(proc)) = (std::fs::metadata("/"), std::fs::metadata("/proc")) else {
+        return;
+    };
+    if root.dev() == proc.dev() {
+        return;
+    }
+
+    let output = Command::new(env!("CARGO_BIN_EXE_finder"))
+        .arg("/")
+        .arg("--one-file-system")
+        .arg("--max-depth")
+        .arg("1")
+        .arg("--verbose")
+        .output()
+        .expect("Failed to execute finder");
+
+    assert!(output.status.success());
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Skipping mount point: /proc"),
+        "got: {}",
+        stderr
+    );
+}
+
+/// Test that auto colour mode writes no escape codes when stdout isn't a
+/// terminal, even if TERM says the terminal supports colour
+#[test]
+fn test_auto_colour_off_when_piped_cli() {
+    let output = Command::new(env!("CARGO_BIN_EXE_finder"))
+        .arg("src")
+        .arg("--search-pattern")
+        .arg("fn ")
+        .env("TERM", "xterm-256color")
+        .env_remove("NO_COLOR")
+        .env_remove("CLICOLOR")
+        .env_remove("CLICOLOR_FORCE")
+        .output()
+        .expect("Failed to execute finder");
+
+    assert!(output.status.success());
+    assert!(!output.stdout.is_empty());
+    assert!(!output.stdout.contains(&b'\x1b'));
+}
+
+/// Test that file-listing mode produces complete JSON output
+#[test]
+fn test_file_listing_json_cli() {
+    let output = Command::new(env!("CARGO_BIN_EXE_finder"))
+        .arg("src")
+        .arg("--file-pattern")
+        .arg("lib.rs")
+        .arg("--json")
+        .output()
+        .expect("Failed to execute finder");
+
+    assert!(output.status.success());
+
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("Output should be valid JSON");
+    assert_eq!(json["files"][0]["path"], "src/lib.rs", "got: {}", json);
+}
+
+/// Test that --sort gives path-ordered output even when searching in parallel
+#[test]
+fn test_sorted_parallel_search_cli() {
+    let output = Command::new(env!("CARGO_BIN_EXE_finder"))
+        .arg("src")
+        .arg("--search-pattern")
+        .arg("fn ")
+        .arg("--threads")
+        .arg("4")
+        .arg("--sort")
+        .arg("path")
+        .arg("--no-colour")
+        .output()
+        .expect("Failed to execute finder");
+
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let paths: Vec<&str> = stdout
+        .lines()
+        .map(|line| line.split(':').next().unwrap())
+        .collect();
+    let mut sorted = paths.clone();
+    sorted.sort();
+    assert!(paths.len() > 10, "got: {}", stdout);
+    assert_eq!(paths, sorted);
+}
+
+/// Test that --json and --sarif keep the requested order instead of sorting by path
+#[test]
+fn test_sorted_json_and_sarif_cli() {
+    let fixture = Fixture::new()
+        .file("a.txt", "needle\n")
+        .file("b.txt", "needle\nand more padding to be the largest\n")
+        .file("c.txt", "needle plus a little\n");
+    let dir = fixture.path();
+
+    let run = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_finder"))
+            .arg(dir)
+            .args(["--search-pattern", "needle", "--sortr", "size"])
+            .args(args)
+            .output()
+            .expect("Failed to execute finder");
+        assert!(output.status.success());
+        serde_json::from_slice::<serde_json::Value>(&output.stdout)
+            .expect("Output should be valid JSON")
+    };
+    let names = |paths: Vec<&str>| -> Vec<String> {
+        paths
+            .iter()
+            .map(|path| path.rsplit('/').next().unwrap().to_string())
+            .collect()
+    };
+
+    let json = run(&["--json"]);
+    let paths = json["files"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|file| file["path"].as_str().unwrap())
+        .collect();
+    assert_eq!(names(paths), ["b.txt", "c.txt", "a.txt"], "got: {}", json);
+
+    let sarif = run(&["--sarif"]);
+    let uris = sarif["runs"][0]["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|result| {
+            result["locations"][0]["physicalLocation"]["artifactLocation"]["uri"]
+                .as_str()
+                .unwrap()
+        })
+        .collect();
+    assert_eq!(names(uris), ["b.txt", "c.txt", "a.txt"], "got: {}", sarif);
+}
+
+/// Test that --sortr reverses the order of file listings
+#[test]
+fn test_reverse_sorted_listing_cli() {
+    let output = Command::new(env!("CARGO_BIN_EXE_finder"))
+        .arg("src")
+        .arg("--file-pattern")
+        .arg(".rs")
+        .arg("--sortr")
+        .arg("path")
+        .arg("--no-colour")
+        .output()
+        .expect("Failed to execute finder");
+
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let paths: Vec<&str> = stdout.lines().collect();
+    let mut reversed = paths.clone();
+    reversed.sort_by(|a, b| b.cmp(a));
+    assert!(paths.len() > 1, "got: {}", stdout);
+    assert_eq!(paths, reversed);
+}
+
+/// Test that non-UTF-8 file names are printed as raw bytes, also in CSV, and
+/// round-trip through JSON via `path_base64`
+#[cfg(unix)]
+#[test]
+fn test_non_utf8_file_names_cli() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let fixture = Fixture::new().file(OsStr::from_bytes(b"caf\xe9.txt"), "needle");
+    let dir = fixture.path();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_finder"))
+        .arg(dir)
+        .arg("--file-pattern")
+        .arg("caf")
+        .arg("--no-colour")
+        .output()
+        .expect("Failed to execute finder");
+    let json_output = Command::new(env!("CARGO_BIN_EXE_finder"))
+        .arg(dir)
+        .arg("--search-pattern")
+        .arg("needle")
+        .arg("--json")
+        .output()
+        .expect("Failed to execute finder");
+    let csv_output = Command::new(env!("CARGO_BIN_EXE_finder"))
+        .arg(dir)
+        .arg("--search-pattern")
+        .arg("needle")
+        .arg("--csv")
+        .arg("--columns")
+        .arg("path,line")
+        .output()
+        .expect("Failed to execute finder");
+
+    assert!(output.status.success());
+    assert!(
+        output.stdout.ends_with(b"/caf\xe9.txt\n"),
+        "got: {:?}",
+        output.stdout
+    );
+    assert!(
+        csv_output.stdout.ends_with(b"/caf\xe9.txt,1\n"),
+        "got: {:?}",
+        csv_output.stdout
+    );
+
+    let json: serde_json::Value =
+        serde_json::from_slice(&json_output.stdout).expect("Output should be valid JSON");
+    assert!(json["files"][0]["path_base64"].is_string(), "got: {}", json);
+    assert_eq!(json["files"][0]["matches"][0]["line"], 1);
+}
+
+/// Test that -0 separates listed paths with NUL bytes, and that the list
+/// can be fed back in with --files-from - --null-data
+#[test]
+fn test_print0_and_files_from_cli() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let fixture = Fixture::new()
+        .file("with space.txt", "needle")
+        .file("new\nline.txt", "needle\nneedle")
+        .file("other.txt", "haystack");
+    let dir = fixture.path();
+
+    let listing = Command::new(env!("CARGO_BIN_EXE_finder"))
+        .arg(dir)
+        .arg("--print0")
+        .arg("--sort")
+        .arg("path")
+        .output()
+        .expect("Failed to execute finder");
+    assert!(listing.status.success());
+    let listed: Vec<&[u8]> = listing.stdout.split(|b| *b == b'\0').collect();
+    assert_eq!(listed.len(), 4, "got: {:?}", listing.stdout);
+    assert!(listed[0].ends_with(b"new\nline.txt"));
+    assert!(listed[3].is_empty());
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_finder"))
+        .arg("--files-from")
+        .arg("-")
+        .arg("--null-data")
+        .arg("--search-pattern")
+        .arg("needle")
+        .arg("--count")
+        .arg("-0")
+        .arg("--sort")
+        .arg("path")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("Failed to execute finder");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(&listing.stdout)
+        .unwrap();
+    let counts = child.wait_with_output().unwrap();
+
+    assert!(counts.status.success());
+    let expected = format!(
+        "{}\x002\n{}\x001\n",
+        dir.join("new\nline.txt").display(),
+        dir.join("with space.txt").display()
+    );
+    assert_eq!(String::from_utf8_lossy(&counts.stdout), expected);
+}
+
+/// Test that --files-from can't be combined with search paths
+#[test]
+fn test_files_from_conflicts_with_paths_cli() {
+    let output = Command::new(env!("CARGO_BIN_EXE_finder"))
+        .arg("src")
+        .arg("--files-from")
+        .arg("list.txt")
+        .output()
+        .expect("Failed to execute finder");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("cannot be used with"), "got: {}", stderr);
+}
+
+/// Test that --exec runs once per file with placeholders expanded, keeping
+/// each command's output intact, and fails if any command fails
+#[cfg(unix)]
+#[test]
+fn test_exec_cli() {
+    let fixture = (0..20).fold(Fixture::new(), |fixture, i| {
+        fixture.file(format!("file{}.txt", i), "")
+    });
+    let dir = fixture.path();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_finder"))
+        .arg(dir)
+        .arg("--threads")
+        .arg("4")
+        .arg("--exec")
+        .arg("sh")
+        .arg("-c")
+        .arg("printf 'start %s\\n' \"$0\"; printf 'end %s\\n' \"$1\"")
+        .arg("{/.}")
+        .arg("{/}")
+        .output()
+        .expect("Failed to execute finder");
+    let failing = Command::new(env!("CARGO_BIN_EXE_finder"))
+        .arg(dir)
+        .arg("--exec")
+        .arg("sh")
+        .arg("-c")
+        .arg("test \"$0\" != file3")
+        .arg("{/.}")
+        .output()
+        .expect("Failed to execute finder");
+
+    assert!(output.status.success());
+    assert_eq!(failing.status.code(), Some(1));
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 40, "got: {}", stdout);
+    for pair in lines.chunks(2) {
+        let stem = pair[0].strip_prefix("start ").unwrap();
+        assert_eq!(pair[1], format!("end {}.txt", stem));
+    }
+}
+
+/// Test that --exec-batch passes every file to a single invocation
+#[cfg(unix)]
+#[test]
+fn test_exec_batch_cli() {
+    let output = Command::new(env!("CARGO_BIN_EXE_finder"))
+        .arg("src/output")
+        .arg("--exec-batch")
+        .arg("echo")
+        .arg("files:")
+        .output()
+        .expect("Failed to execute finder");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.starts_with("files: src/output/colour.rs src/output/count.rs"),
+        "got: {}",
+        stdout
+    );
+    assert_eq!(stdout.lines().count(), 1);
+}
+
+/// Test that --kind lists directories, and can't be used to search contents
+#[test]
+fn test_kind_cli() {
+    let output = Command::new(env!("CARGO_BIN_EXE_finder"))
+        .arg("src")
+        .arg("--kind")
+        .arg("d")
+        .arg("--sort")
+        .arg("path")
+        .output()
+        .expect("Failed to execute finder");
+    let searching = Command::new(env!("CARGO_BIN_EXE_finder"))
+        .arg("src")
+        .arg("--kind")
+        .arg("d")
+        .arg("--search-pattern")
+        .arg("fn")
+        .output()
+        .expect("Failed to execute finder");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        stdout.lines().collect::<Vec<_>>(),
+        vec!["src/bin", "src/file_finder", "src/output"]
+    );
+
+    assert!(!searching.status.success());
+    let stderr = String::from_utf8_lossy(&searching.stderr);
+    assert!(stderr.contains("--kind"), "got: {}", stderr);
+}
+
+/// Test that --long lists permissions, size, mtime and path, and that
+/// --long --json adds the same fields as metadata
+#[test]
+fn test_long_listing_cli() {
+    let fixture = Fixture::new().file("data.bin", [0u8; 4096]);
+    let dir = fixture.path();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_finder"))
+        .arg(dir)
+        .arg("--long")
+        .output()
+        .expect("Failed to execute finder");
+    let json_output = Command::new(env!("CARGO_BIN_EXE_finder"))
+        .arg(dir)
+        .arg("--long")
+        .arg("--json")
+        .output()
+        .expect("Failed to execute finder");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let fields: Vec<&str> = stdout.split_whitespace().collect();
+    assert_eq!(fields.len(), 5, "got: {}", stdout);
+    assert!(fields[0].starts_with('-') && fields[0].len() == 10);
+    assert_eq!(fields[1], "4.0K");
+    assert_eq!(fields[2].len(), "2026-01-01".len());
+    assert!(fields[4].ends_with("data.bin"));
+
+    let json: serde_json::Value =
+        serde_json::from_slice(&json_output.stdout).expect("Output should be valid JSON");
+    let metadata = &json["files"][0]["metadata"];
+    assert_eq!(metadata["size"], 4096, "got: {}", json);
+    assert_eq!(metadata["permissions"], fields[0]);
+    assert!(metadata["modified"].as_str().unwrap().ends_with('Z'));
+}
+
+/// Test that --jsonl writes one valid object per line, with each file's
+/// matches between its begin and end events and a summary last
+#[test]
+fn test_jsonl_output_cli() {
+    let output = Command::new(env!("CARGO_BIN_EXE_finder"))
+        .arg("src")
+        .arg("--search-pattern")
+        .arg("fn ")
+        .arg("--jsonl")
+        .output()
+        .expect("Failed to execute finder");
+
+    assert!(output.status.success());
+
+    let events: Vec<serde_json::Value> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("Each line should be valid JSON"))
+        .collect();
+
+    let mut current: Option<String> = None;
+    let mut count = 0;
+    let mut total = 0;
+    for event in &events[..events.len() - 1] {
+        let path = event["path"].as_str().unwrap().to_string();
+        match event["type"].as_str().unwrap() {
+            "begin" => {
+                assert!(current.is_none(), "begin inside another file");
+                current = Some(path);
+                count = 0;
+            }
+            "match" => {
+                assert_eq!(current.as_ref(), Some(&path));
+                assert!(event["line"].as_u64().unwrap() > 0);
+                count += 1;
+                total += 1;
+            }
+            "end" => {
+                assert_eq!(current.take(), Some(path));
+                assert_eq!(event["matches"], count);
+            }
+            other => panic!("unexpected event {}", other),
+        }
+    }
+
+    let summary = events.last().unwrap();
+    assert_eq!(summary["type"], "summary");
+    assert_eq!(summary["matches"], total);
+    assert!(total > 0);
+}
+
+/// Test the versioned JSON document: spans, captures, counts, summary and errors
+#[test]
+fn test_json_schema_cli() {
+    let fixture = Fixture::new()
+        .file("a.txt", "größe=12 breite=7\nnothing\n")
+        .file("b.txt", b"\xff\xfe\nheight=3\n")
+        .file("c.txt", "no values here\n");
+    let dir = fixture.path();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_finder"))
+        .arg(dir)
+        .arg("--regex-pattern")
+        .arg(r"(?P<key>\w+)=(?P<value>\d+)")
+        .arg("--json")
+        .arg("--sort")
+        .arg("path")
+        .output()
+        .expect("Failed to execute finder");
+
+    assert!(output.status.success());
+
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("Output should be valid JSON");
+    assert_eq!(json["version"], 2);
+
+    let file = &json["files"][0];
+    assert_eq!(file["matched_lines"], 1, "got: {}", json);
+    assert_eq!(file["match_count"], 2);
+    let submatch = &file["matches"][0]["submatches"][1];
+    assert_eq!(submatch["text"], "breite=7");
+    assert_eq!(submatch["start"], 11);
+    assert_eq!(submatch["char_start"], 9);
+    assert_eq!(submatch["captures"]["value"]["text"], "7");
+
+    let summary = &json["summary"];
+    assert_eq!(summary["files"], 2);
+    assert_eq!(summary["files_searched"], 3);
+    assert_eq!(summary["matches"], 3);
+    assert_eq!(summary["bytes_searched"], 55);
+
+    let errors = json["errors"].as_array().unwrap();
+    assert_eq!(errors.len(), 1, "got: {}", json);
+    assert!(errors[0]["path"].as_str().unwrap().ends_with("b.txt"));
+    assert!(
+        errors[0]["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("line 1:")
+    );
+}
+
+/// Test that -i reports spans in the original line when lowercasing changes its length
+#[test]
+fn test_case_insensitive_json_spans_cli() {
+    let fixture = Fixture::new().file("a.txt", "İİ NEEDLE x\n");
+    let dir = fixture.path();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_finder"))
+        .arg(dir)
+        .arg("-i")
+        .arg("--search-pattern")
+        .arg("needle")
+        .arg("--json")
+        .output()
+        .expect("Failed to execute finder");
+
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("Output should be valid JSON");
+    let submatch = &json["files"][0]["matches"][0]["submatches"][0];
+    assert_eq!(submatch["text"], "NEEDLE", "got: {}", json);
+    assert_eq!(submatch["start"], 5);
+    assert_eq!(submatch["char_start"], 3);
+}
+
+/// Test that --json-format rg writes ripgrep's begin/match/end/summary messages
+#[test]
+fn test_rg_json_format_cli() {
+    let fixture = Fixture::new().file("a.txt", "first\r\nneedle and needle\n");
+    let dir = fixture.path();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_finder"))
+        .arg(dir)
+        .arg("--search-pattern")
+        .arg("needle")
+        .arg("--json-format")
+        .arg("rg")
+        .output()
+        .expect("Failed to execute finder");
+
+    assert!(output.status.success());
+
+    let messages: Vec<serde_json::Value> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("Each line should be valid JSON"))
+        .collect();
+    let types: Vec<&str> = messages
+        .iter()
+        .map(|message| message["type"].as_str().unwrap())
+        .collect();
+    assert_eq!(types, ["begin", "match", "end", "summary"]);
+
+    let data = &messages[1]["data"];
+    assert!(data["path"]["text"].as_str().unwrap().ends_with("a.txt"));
+    assert_eq!(data["lines"]["text"], "needle and needle\n");
+    assert_eq!(data["line_number"], 2);
+    assert_eq!(data["absolute_offset"], 7);
+    assert_eq!(data["submatches"][1]["match"]["text"], "needle");
+    assert_eq!(data["submatches"][1]["start"], 11);
+
+    let stats = &messages[3]["data"]["stats"];
+    assert_eq!(stats["searches"], 1);
+    assert_eq!(stats["matches"], 2);
+    assert_eq!(stats["bytes_searched"], 25);
+    assert!(messages[3]["data"]["elapsed_total"]["human"].is_string());
+}
+
+/// Test that --json-format rg writes lines with their terminators exactly as read
+#[test]
+fn test_rg_json_line_endings_cli() {
+    let fixture = Fixture::new().file("a.txt", b"crlf needle\r\ncaf\xe9 needle\r\nlast needle");
+    let dir = fixture.path();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_finder"))
+        .arg(dir)
+        .arg("--search-pattern")
+        .arg("needle")
+        .arg("--json-format")
+        .arg("rg")
+        .output()
+        .expect("Failed to execute finder");
+
+    assert!(output.status.success());
+
+    let lines: Vec<serde_json::Value> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .filter(|message| message["type"] == "match")
+        .map(|message| message["data"]["lines"].clone())
+        .collect();
+    assert_eq!(lines.len(), 3, "got: {:?}", lines);
+    assert_eq!(lines[0]["text"], "crlf needle\r\n");
+    // base64 of b"caf\xe9 needle\r\n"
+    assert_eq!(lines[1]["bytes"], "Y2Fm6SBuZWVkbGUNCg==");
+    assert_eq!(lines[2]["text"], "last needle");
+}
+
+/// Test that --json-format rg reports lines that aren't valid UTF-8 as base64 bytes
+#[test]
+fn test_rg_json_non_utf8_lines_cli() {
+    let fixture = Fixture::new().file("a.txt", b"caf\xe9 needle\n");
+    let dir = fixture.path();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_finder"))
+        .arg(dir)
+        .arg("--search-pattern")
+        .arg("needle")
+        .arg("--json-format")
+        .arg("rg")
+        .output()
+        .expect("Failed to execute finder");
+
+    assert!(output.status.success());
+
+    let messages: Vec<serde_json::Value> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("Each line should be valid JSON"))
+        .collect();
+    let data = &messages[1]["data"];
+    assert_eq!(messages[1]["type"], "match", "got: {:?}", messages);
+    // base64 of b"caf\xe9 needle\n"
+    assert_eq!(data["lines"]["bytes"], "Y2Fm6SBuZWVkbGUK");
+    assert_eq!(data["submatches"][0]["match"]["text"], "needle");
+    assert_eq!(data["submatches"][0]["start"], 5);
+    assert_eq!(data["submatches"][0]["end"], 11);
+}
+
+/// Test that --sarif output is a valid SARIF 2.1.0 log with one result per match
+#[test]
+fn test_sarif_output_cli() {
+    let fixture = Fixture::new()
+        .file("main file.rs", "ok\nlet größe = a.unwrap() + b.unwrap();\n")
+        .file("bad.rs", b"\xff\nc.unwrap()\n");
+    let dir = fixture.path();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_finder"))
+        .arg(dir)
+        .arg("--regex-pattern")
+        .arg(r"\.unwrap\(\)")
+        .arg("--sarif")
+        .output()
+        .expect("Failed to execute finder");
+
+    assert!(output.status.success());
+
+    let sarif: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("Output should be valid JSON");
+    // The fixture restates the OASIS schema's definitions for the objects
+    // finders writes; it isn't the unchanged official file.
+    // TODO: vendor the official sarif-schema-2.1.0.json and validate against it
+    let schema: serde_json::Value =
+        serde_json::from_str(include_str!("fixtures/sarif-schema-2.1.0-subset.json")).unwrap();
+    let validator = jsonschema::options()
+        .should_validate_formats(true)
+        .build(&schema)
+        .expect("Schema should compile");
+    let errors: Vec<String> = validator
+        .iter_errors(&sarif)
+        .map(|error| format!("{} at {}", error, error.instance_path()))
+        .collect();
+    assert!(errors.is_empty(), "invalid SARIF: {:?}\n{}", errors, sarif);
+
+    // The schema catches mistakes, e.g. 0-based columns or unknown properties
+    let mut broken = sarif.clone();
+    broken["runs"][0]["results"][0]["locations"][0]["physicalLocation"]["region"]["startColumn"] =
+        0.into();
+    broken["runs"][0]["columns"] = "utf8".into();
+    assert_eq!(validator.iter_errors(&broken).count(), 2);
+
+    let run = &sarif["runs"][0];
+    assert_eq!(run["tool"]["driver"]["rules"][0]["id"], "unwrap");
+    let results = run["results"].as_array().unwrap();
+    assert_eq!(results.len(), 3, "got: {}", sarif);
+
+    // Results are sorted by path, so the two matches in "main file.rs" come last
+    let region = &results[2]["locations"][0]["physicalLocation"]["region"];
+    assert_eq!(region["startLine"], 2);
+    assert_eq!(region["startColumn"], 27);
+    assert_eq!(region["endColumn"], 36);
+    let uri = results[2]["locations"][0]["physicalLocation"]["artifactLocation"]["uri"]
+        .as_str()
+        .unwrap();
+    assert!(uri.ends_with("/main%20file.rs"), "got: {}", uri);
+
+    let notifications = run["invocations"][0]["toolExecutionNotifications"]
+        .as_array()
+        .unwrap();
+    assert_eq!(notifications.len(), 1);
+}
+
+/// Test that --csv quotes fields, writes one row per match and lists
+/// metadata columns in file-listing mode; --tsv uses tabs
+#[test]
+fn test_csv_output_cli() {
+    let fixture = Fixture::new().file("data.txt", "say \"hi\", then x\nno match\nx\tx\n");
+    let dir = fixture.path();
+    let file = dir.join("data.txt");
+
+    let run = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_finder"))
+            .arg(dir)
+            .args(args)
+            .output()
+            .expect("Failed to execute finder");
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+    let search = run(&["--search-pattern", "x", "--csv"]);
+    let lines = run(&[
+        "--search-pattern",
+        "x",
+        "--csv",
+        "--columns",
+        "line,content",
+    ]);
+    let tsv = run(&["--search-pattern", "x", "--tsv", "--columns", "line,column"]);
+    let listing = run(&["--csv"]);
+
+    let path = file.to_str().unwrap();
+
+    assert_eq!(
+        search,
+        format!(
+            "path,line,column,match,content\n\
+             {path},1,16,x,\"say \"\"hi\"\", then x\"\n\
+             {path},3,1,x,x\tx\n\
+             {path},3,3,x,x\tx\n"
+        )
+    );
+    // Without column or match, each line is one row
+    assert_eq!(
+        lines,
+        "line,content\n1,\"say \"\"hi\"\", then x\"\n3,x\tx\n"
+    );
+    assert_eq!(tsv, "line\tcolumn\n1\t16\n3\t1\n3\t3\n");
+
+    let mut listing = listing.lines();
+    assert_eq!(listing.next(), Some("path,size,modified,permissions"));
+    let row: Vec<&str> = listing.next().unwrap().split(',').collect();
+    assert_eq!(row[0], path);
+    assert_eq!(row[1], "30");
+    assert!(row[2].ends_with('Z'));
+}
+
+/// Test that --vimgrep writes one uncoloured entry per match with 1-based
+/// byte columns, in path order
+#[test]
+fn test_vimgrep_output_cli() {
+    let fixture = ["c.txt", "a.txt", "b.txt"]
+        .into_iter()
+        .fold(Fixture::new(), |fixture, name| {
+            fixture.file(name, "todo: x, todo: y\nnothing\ncafé todo\n")
+        });
+    let dir = fixture.path();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_finder"))
+        .arg(dir)
+        .args(["--search-pattern", "todo", "--vimgrep", "--colour"])
+        .output()
+        .expect("Failed to execute finder");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(!stdout.contains('\x1b'), "got: {}", stdout);
+
+    let expected: String = ["a.txt", "b.txt", "c.txt"]
+        .iter()
+        .map(|name| {
+            let path = dir.join(name);
+            let path = path.to_str().unwrap();
+            format!(
+                "{path}:1:1:todo: x, todo: y\n\
+                 {path}:1:10:todo: x, todo: y\n\
+                 {path}:3:7:café todo\n"
+            )
+        })
+        .collect();
+    assert_eq!(stdout, expected);
+}
+
+/// Test that --heading prints each path once above its matches, with a
+/// blank line between files, and --no-heading overrides it
+#[test]
+fn test_heading_output_cli() {
+    let fixture = Fixture::new()
+        .file("a.txt", "todo one\nnothing\ntodo two\n")
+        .file("b.txt", "last todo\n");
+    let dir = fixture.path();
+
+    let run = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_finder"))
+            .arg(dir)
+            .args(["--search-pattern", "todo", "--sort", "path"])
+            .args(args)
+            .output()
+            .expect("Failed to execute finder");
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+    let heading = run(&["--heading"]);
+    let no_heading = run(&["--heading", "--no-heading"]);
+    // Not a terminal, so no headings by default
+    let default = run(&[]);
+
+    let a = dir.join("a.txt");
+    let b = dir.join("b.txt");
+    let (a, b) = (a.to_str().unwrap(), b.to_str().unwrap());
+
+    assert_eq!(
+        heading,
+        format!("{a}\n1: todo one\n3: todo two\n\n{b}\n1: last todo\n")
+    );
+    assert_eq!(
+        no_heading,
+        format!("{a}:1: todo one\n{a}:3: todo two\n{b}:1: last todo\n")
+    );
+    assert_eq!(default, no_heading);
+}
+
+/// Test that --hyperlink-format wraps paths and line numbers in OSC 8
+/// hyperlinks when colour is on, and is ignored when it's off
+#[test]
+fn test_hyperlink_format_cli() {
+    let fixture = Fixture::new().file("a b.txt", "nothing\nsome todo here\n");
+    let dir = fixture.path();
+
+    let run = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_finder"))
+            .arg(dir)
+            .args(["--search-pattern", "todo"])
+            .args(args)
+            .output()
+            .expect("Failed to execute finder");
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+    let vscode = run(&["--colour", "--hyperlink-format", "vscode"]);
+    let files = run(&["--colour", "-l", "--hyperlink-format", "file"]);
+    let plain = run(&["--no-colour", "--hyperlink-format", "vscode"]);
+    let custom = Command::new(env!("CARGO_BIN_EXE_finder"))
+        .arg(dir)
+        .args(["--search-pattern", "todo", "--hyperlink-format", "{line}"])
+        .output()
+        .expect("Failed to execute finder");
+
+    let encoded = format!("{}/a%20b.txt", dir.to_str().unwrap());
+
+    // Both the path and the line number link to line 2, column 6
+    let link = format!("\x1b]8;;vscode://file{}:2:6\x1b\\", encoded);
+    assert_eq!(vscode.matches(&link).count(), 2, "got: {:?}", vscode);
+    assert_eq!(vscode.matches("\x1b]8;;\x1b\\").count(), 2);
+
+    assert!(files.starts_with(&format!("\x1b]8;;file://{}\x1b\\", encoded)));
+    assert!(!plain.contains('\x1b'));
+    assert!(!custom.status.success());
+}
+
+/// Test that --colors and FINDERS_COLORS change the colours of every
+/// coloured output, with --colors applied last
+#[test]
+fn test_colour_specs_cli() {
+    let fixture = Fixture::new().file("a.txt", "a todo\n");
+    let dir = fixture.path();
+
+    let run = |env: &str, args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_finder"))
+            .arg(dir)
+            .args(["--colour"])
+            .args(args)
+            .env("FINDERS_COLORS", env)
+            .output()
+            .expect("Failed to execute finder");
+        (
+            output.status.success(),
+            String::from_utf8(output.stdout).unwrap(),
+        )
+    };
+    let (_, standard) = run(
+        "path:fg:magenta; match:none",
+        &[
+            "-s",
+            "todo",
+            "--colors",
+            "line:fg:#102030",
+            "--colors",
+            "match:style:underline",
+        ],
+    );
+    let (_, count) = run("path:fg:208", &["-s", "todo", "-c"]);
+    let (_, files) = run(
+        "path:fg:208",
+        &["-s", "todo", "-l", "--colors", "path:none"],
+    );
+    let (_, long) = run("", &["--long", "--colors", "path:fg:1,2,3"]);
+    let (ok, _) = run("path:fg:mauve", &["-s", "todo"]);
+
+    assert!(standard.contains("\x1b[35m"), "got: {:?}", standard);
+    assert!(standard.contains("\x1b[38;2;16;32;48m1"));
+    // match:none cleared the blue background before underline was added
+    assert!(standard.contains("\x1b[4mtodo"));
+    assert!(!standard.contains("\x1b[44m"));
+    assert!(count.contains("\x1b[38;5;208m"));
+    assert!(!files.contains("\x1b[38;5;208m"));
+    assert!(!files.contains("\x1b[32m"));
+    assert!(long.contains("\x1b[38;2;1;2;3m"));
+    assert!(!ok);
+}
+
+/// Test that --format renders a template per match, with per-file header
+/// and footer templates, captures and file placeholders
+#[test]
+fn test_format_template_cli() {
+    let fixture = Fixture::new().file("a.rs", "let x = 1; let yy = 2;\nnothing\nlet z = 3;\n");
+    let dir = fixture.path();
+
+    let run = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_finder"))
+            .arg(dir)
+            .args(args)
+            .output()
+            .expect("Failed to execute finder");
+        (
+            output.status.success(),
+            String::from_utf8(output.stdout).unwrap(),
+        )
+    };
+    let (_, matches) = run(&[
+        "-r",
+        r"let (?<name>\w+)",
+        "--format",
+        r"{line}:{col}\t{capture:name}",
+        "--format-header",
+        "== {name} ({ext}, {size} bytes)",
+        "--format-footer",
+        "== {count} in {lines} lines",
+    ]);
+    let (_, lines) = run(&["-s", "let", "--format", "{stem}:{line}: {content}"]);
+    let (_, listing) = run(&["--format", "{{{name}}} {dir}"]);
+    let (header_ok, _) = run(&[
+        "-s",
+        "let",
+        "--format",
+        "{path}",
+        "--format-header",
+        "{line}",
+    ]);
+    let (listing_ok, _) = run(&["--format", "{match}"]);
+    let (count_ok, _) = run(&["-s", "let", "--format", "{count}"]);
+
+    assert_eq!(
+        matches,
+        "== a.rs (rs, 42 bytes)\n1:1\tx\n1:12\tyy\n3:1\tz\n== 3 in 2 lines\n"
+    );
+    assert_eq!(lines, "a:1: let x = 1; let yy = 2;\na:3: let z = 3;\n");
+    assert_eq!(listing, format!("{{a.rs}} {}\n", dir.to_str().unwrap()));
+    assert!(!header_ok);
+    assert!(!listing_ok);
+    assert!(!count_ok);
+}
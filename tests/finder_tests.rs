@@ -0,0 +1,391 @@
+use finders::file_finder::{Finder, MetadataFilter};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Create a fresh directory under the system temp dir for a single test
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("finders_{}", name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).expect("Should create test directory");
+    dir
+}
+
+fn file_names(paths: &[PathBuf]) -> Vec<String> {
+    let mut names: Vec<String> = paths
+        .iter()
+        .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+        .collect();
+    names.sort();
+    names
+}
+
+/// Test that size filters compose with the filename filter
+#[test]
+fn test_finder_size_filter() {
+    let dir = test_dir("size_filter");
+    fs::write(dir.join("small.log"), "x").unwrap();
+    fs::write(dir.join("large.log"), "x".repeat(4096)).unwrap();
+    fs::write(dir.join("large.txt"), "x".repeat(4096)).unwrap();
+
+    let finder = Finder::new(dir.to_str())
+        .unwrap()
+        .filters(vec![MetadataFilter::parse_size("+1k").unwrap()]);
+    let results = finder.find(Some(".log"), false);
+
+    let _ = fs::remove_dir_all(&dir);
+    assert_eq!(file_names(&results), vec!["large.log"]);
+}
+
+/// Test that modification time filters use the reference file's mtime
+#[test]
+fn test_finder_newer_filter() {
+    let dir = test_dir("newer_filter");
+    fs::write(dir.join("old.txt"), "old").unwrap();
+
+    let reference = dir.join("old.txt");
+    let filter = MetadataFilter::newer_than(Path::new(&reference)).unwrap();
+
+    let finder = Finder::new(dir.to_str()).unwrap().filters(vec![filter]);
+    let results = finder.find(None, false);
+
+    let _ = fs::remove_dir_all(&dir);
+    assert!(
+        results.is_empty(),
+        "Reference file is not newer than itself"
+    );
+}
+
+/// Test that the executable filter only returns files with an execute bit
+#[cfg(unix)]
+#[test]
+fn test_finder_executable_filter() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = test_dir("executable_filter");
+    fs::write(dir.join("script.sh"), "#!/bin/sh").unwrap();
+    fs::write(dir.join("notes.txt"), "notes").unwrap();
+    fs::set_permissions(dir.join("script.sh"), fs::Permissions::from_mode(0o755)).unwrap();
+    fs::set_permissions(dir.join("notes.txt"), fs::Permissions::from_mode(0o644)).unwrap();
+
+    let finder = Finder::new(dir.to_str())
+        .unwrap()
+        .filters(vec![MetadataFilter::Executable]);
+    let executables = finder.find(None, false);
+
+    let finder = Finder::new(dir.to_str())
+        .unwrap()
+        .filters(vec![MetadataFilter::parse_permissions("644").unwrap()]);
+    let readable = finder.find(None, false);
+
+    let _ = fs::remove_dir_all(&dir);
+    assert_eq!(file_names(&executables), vec!["script.sh"]);
+    assert_eq!(file_names(&readable), vec!["notes.txt"]);
+}
+
+/// Test that file type selection integrates with the filename filter
+#[test]
+fn test_finder_type_filter() {
+    use finders::file_finder::TypeRegistry;
+
+    let dir = test_dir("type_filter");
+    fs::write(dir.join("main.rs"), "fn main() {}").unwrap();
+    fs::write(dir.join("test_main.rs"), "#[test]").unwrap();
+    fs::write(dir.join("Makefile"), "all:").unwrap();
+    fs::write(dir.join("notes.txt"), "notes").unwrap();
+
+    let registry = TypeRegistry::new();
+    let types = registry
+        .matcher(&["rust".to_string(), "make".to_string()], &[])
+        .unwrap();
+    let all = Finder::new(dir.to_str())
+        .unwrap()
+        .types(types.clone())
+        .find(None, false);
+    let tests = Finder::new(dir.to_str())
+        .unwrap()
+        .types(types)
+        .find(Some("test"), false);
+
+    let _ = fs::remove_dir_all(&dir);
+    assert_eq!(
+        file_names(&all),
+        vec!["Makefile", "main.rs", "test_main.rs"]
+    );
+    assert_eq!(file_names(&tests), vec!["test_main.rs"]);
+}
+
+/// Test that depth limits restrict which levels of the tree are returned
+#[test]
+fn test_finder_depth_limits() {
+    let dir = test_dir("depth_limits");
+    fs::create_dir_all(dir.join("a/b")).unwrap();
+    fs::write(dir.join("top.txt"), "").unwrap();
+    fs::write(dir.join("a/middle.txt"), "").unwrap();
+    fs::write(dir.join("a/b/deep.txt"), "").unwrap();
+
+    let shallow = Finder::new(dir.to_str())
+        .unwrap()
+        .max_depth(Some(1))
+        .find(None, false);
+    let nested = Finder::new(dir.to_str())
+        .unwrap()
+        .min_depth(Some(2))
+        .find(None, false);
+    let middle = Finder::new(dir.to_str())
+        .unwrap()
+        .min_depth(Some(2))
+        .max_depth(Some(2))
+        .find(None, false);
+
+    let _ = fs::remove_dir_all(&dir);
+    assert_eq!(file_names(&shallow), vec!["top.txt"]);
+    assert_eq!(file_names(&nested), vec!["deep.txt", "middle.txt"]);
+    assert_eq!(file_names(&middle), vec!["middle.txt"]);
+}
+
+/// Test that hidden entries are skipped by default and .git is always skipped
+#[test]
+fn test_finder_hidden_policy() {
+    let dir = test_dir("hidden_policy");
+    fs::create_dir_all(dir.join(".git")).unwrap();
+    fs::create_dir_all(dir.join(".cache")).unwrap();
+    fs::write(dir.join("visible.txt"), "").unwrap();
+    fs::write(dir.join(".env"), "").unwrap();
+    fs::write(dir.join(".cache/cached.txt"), "").unwrap();
+    fs::write(dir.join(".git/HEAD"), "").unwrap();
+
+    let default = Finder::new(dir.to_str()).unwrap().find(None, false);
+    let hidden = Finder::new(dir.to_str())
+        .unwrap()
+        .hidden(true)
+        .find(None, false);
+    let git_root = dir.join(".git");
+    let explicit_git = Finder::new(git_root.to_str()).unwrap().find(None, false);
+
+    let _ = fs::remove_dir_all(&dir);
+    assert_eq!(file_names(&default), vec!["visible.txt"]);
+    assert_eq!(
+        file_names(&hidden),
+        vec![".env", "cached.txt", "visible.txt"]
+    );
+    assert_eq!(file_names(&explicit_git), vec!["HEAD"]);
+}
+
+/// Test that symlinked files are searched once and loops don't hang traversal
+#[cfg(unix)]
+#[test]
+fn test_finder_symlink_handling() {
+    use std::os::unix::fs::symlink;
+
+    let dir = test_dir("symlink_handling");
+    fs::create_dir_all(dir.join("real")).unwrap();
+    fs::write(dir.join("real/target.txt"), "").unwrap();
+    symlink(dir.join("real/target.txt"), dir.join("link_a.txt")).unwrap();
+    symlink(dir.join("real/target.txt"), dir.join("link_b.txt")).unwrap();
+    symlink(dir.join("missing.txt"), dir.join("broken.txt")).unwrap();
+    symlink(&dir, dir.join("real/loop")).unwrap();
+
+    // Both the sequential and parallel walkers must agree
+    for threads in [1, 4] {
+        let followed = Finder::new(dir.to_str())
+            .unwrap()
+            .threads(threads)
+            .find(None, true);
+        let unfollowed = Finder::new(dir.to_str())
+            .unwrap()
+            .threads(threads)
+            .follow_links(false)
+            .find(None, true);
+
+        assert_eq!(followed.len(), 1, "Got: {:?}", followed);
+        assert_eq!(file_names(&unfollowed), vec!["target.txt"]);
+    }
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+/// Test that several roots produce one merged, de-duplicated result set
+#[test]
+fn test_finder_multiple_roots() {
+    let dir = test_dir("multiple_roots");
+    fs::create_dir_all(dir.join("src/nested")).unwrap();
+    fs::create_dir_all(dir.join("tests")).unwrap();
+    fs::write(dir.join("src/lib.rs"), "").unwrap();
+    fs::write(dir.join("src/nested/mod.rs"), "").unwrap();
+    fs::write(dir.join("tests/it.rs"), "").unwrap();
+
+    let src = dir.join("src");
+    let nested = dir.join("src/nested");
+    let tests = dir.join("tests");
+    let results = Finder::with_roots([
+        src.to_str().unwrap(),
+        tests.to_str().unwrap(),
+        nested.to_str().unwrap(),
+        src.to_str().unwrap(),
+    ])
+    .unwrap()
+    .follow_links(false)
+    .find(None, false);
+
+    let _ = fs::remove_dir_all(&dir);
+    assert_eq!(file_names(&results), vec!["it.rs", "lib.rs", "mod.rs"]);
+}
+
+/// Test that the parallel walker returns the same files as the sequential one
+#[test]
+fn test_finder_parallel_matches_sequential() {
+    let dir = test_dir("parallel_walk");
+    for i in 0..20 {
+        let sub = dir.join(format!("dir{}/nested{}", i % 4, i % 3));
+        fs::create_dir_all(&sub).unwrap();
+        fs::write(sub.join(format!("file{}.rs", i)), "x".repeat(i * 100)).unwrap();
+        fs::write(sub.join(format!("file{}.txt", i)), "").unwrap();
+    }
+    fs::write(dir.join(".hidden.rs"), "").unwrap();
+
+    let find = |threads: usize| {
+        let mut results = Finder::new(dir.to_str())
+            .unwrap()
+            .threads(threads)
+            .min_depth(Some(2))
+            .max_depth(Some(3))
+            .filters(vec![MetadataFilter::parse_size("+500").unwrap()])
+            .find(Some(".rs"), false);
+        results.sort();
+        results
+    };
+    let sequential = find(1);
+    let parallel = find(4);
+
+    let _ = fs::remove_dir_all(&dir);
+    assert_eq!(sequential.len(), 14);
+    assert_eq!(sequential, parallel);
+}
+
+/// Test that streamed paths match the collected result, and that a consumer
+/// stopping early doesn't block the walker
+#[test]
+fn test_finder_stream() {
+    let dir = test_dir("stream");
+    for i in 0..2000 {
+        fs::write(dir.join(format!("file{}.txt", i)), "").unwrap();
+    }
+
+    let finder = Finder::new(dir.to_str()).unwrap();
+    let mut streamed = finder.stream(None, false, |paths| paths.collect::<Vec<_>>());
+    let mut collected = finder.find(None, false);
+    let first = finder.stream(None, false, |mut paths| paths.next());
+
+    let _ = fs::remove_dir_all(&dir);
+    streamed.sort();
+    collected.sort();
+    assert_eq!(streamed.len(), 2000);
+    assert_eq!(streamed, collected);
+    assert!(first.is_some());
+}
+
+/// Test that names which aren't valid UTF-8 can be matched by pattern and type
+#[cfg(unix)]
+#[test]
+fn test_finder_non_utf8_names() {
+    use finders::file_finder::TypeRegistry;
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let dir = test_dir("non_utf8_names");
+    let name = OsStr::from_bytes(b"caf\xe9_notes.txt");
+    fs::write(dir.join(name), "").unwrap();
+    fs::write(dir.join("other.rs"), "").unwrap();
+
+    let types = TypeRegistry::new()
+        .matcher(&["txt".to_string()], &[])
+        .unwrap();
+    for threads in [1, 4] {
+        let by_pattern = Finder::new(dir.to_str())
+            .unwrap()
+            .threads(threads)
+            .find(Some("_notes"), false);
+        let by_type = Finder::new(dir.to_str())
+            .unwrap()
+            .threads(threads)
+            .types(types.clone())
+            .find(None, false);
+
+        assert_eq!(by_pattern, vec![dir.join(name)]);
+        assert_eq!(by_type, vec![dir.join(name)]);
+    }
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+/// Test that an explicit file list is filtered without walking directories
+#[test]
+fn test_finder_with_files() {
+    let dir = test_dir("with_files");
+    fs::create_dir_all(dir.join("sub")).unwrap();
+    fs::write(dir.join("b.rs"), "").unwrap();
+    fs::write(dir.join("a.rs"), "").unwrap();
+    fs::write(dir.join("notes.txt"), "").unwrap();
+    fs::write(dir.join("sub/unlisted.rs"), "").unwrap();
+
+    let files = vec![
+        dir.join("b.rs"),
+        dir.join("notes.txt"),
+        dir.join("sub"),
+        dir.join("missing.rs"),
+        dir.join("a.rs"),
+        dir.join("b.rs"),
+    ];
+    let results = Finder::with_files(files).find(Some(".rs"), false);
+
+    let _ = fs::remove_dir_all(&dir);
+    assert_eq!(results, vec![dir.join("b.rs"), dir.join("a.rs")]);
+}
+
+/// Test that entry kinds select directories, symlinks and empty entries
+#[cfg(unix)]
+#[test]
+fn test_finder_kinds() {
+    use finders::file_finder::EntryKind;
+    use std::os::unix::fs::symlink;
+
+    let dir = test_dir("kinds");
+    fs::create_dir_all(dir.join("full/nested_empty")).unwrap();
+    fs::write(dir.join("full/data.txt"), "data").unwrap();
+    fs::write(dir.join("empty.txt"), "").unwrap();
+    symlink(dir.join("full/data.txt"), dir.join("link_a")).unwrap();
+    symlink(dir.join("full/data.txt"), dir.join("link_b")).unwrap();
+    symlink(dir.join("missing"), dir.join("broken")).unwrap();
+
+    for threads in [1, 4] {
+        let find = |kinds: Vec<EntryKind>, query: Option<&str>| {
+            Finder::new(dir.to_str())
+                .unwrap()
+                .threads(threads)
+                .kinds(kinds)
+                .find(query, false)
+        };
+
+        assert_eq!(
+            file_names(&find(vec![EntryKind::Directory], None)),
+            vec!["full", "nested_empty"]
+        );
+        assert_eq!(
+            file_names(&find(vec![EntryKind::Symlink], None)),
+            vec!["broken", "link_a", "link_b"]
+        );
+        assert_eq!(
+            file_names(&find(vec![EntryKind::Empty], None)),
+            vec!["empty.txt", "nested_empty"]
+        );
+        assert_eq!(
+            file_names(&find(
+                vec![EntryKind::Directory, EntryKind::File],
+                Some("e")
+            )),
+            vec!["empty.txt", "nested_empty"]
+        );
+    }
+
+    let _ = fs::remove_dir_all(&dir);
+}